@@ -0,0 +1,62 @@
+// External libraries
+use actix_web::{HttpResponse, http::StatusCode};
+use log::{error, warn};
+use serde_json::json;
+
+// Internal modules
+use crate::models::error::{Error, ErrorFault};
+
+/// Converts a crawler error into the response sent to the client, logging the ones that are not user faults
+pub fn error_response(university: &str, error: Error) -> HttpResponse {
+    match error.fault {
+        ErrorFault::User => {
+            return HttpResponse::build(
+                StatusCode::from_u16(error.http_code.unwrap_or(400)).unwrap(),
+            )
+            .body(json!({ "error": error.error, "message": error.message }).to_string());
+        }
+
+        ErrorFault::Internal => {
+            error!(
+                "Internal error:\nCrawler: {:#?} \n{:#?}\n{:#?}\n{:#?}",
+                university,
+                error.error,
+                error.fault,
+                error.message.unwrap_or("<no message>".to_string())
+            );
+
+            return HttpResponse::build(StatusCode::from_u16(500).unwrap())
+            .body(json!({"error": "Internal ServerError", "message":"An internal error occurred"}).to_string());
+        }
+
+        ErrorFault::External => {
+            // The university is failing or we are holding requests back, no need for the full report
+            if error.http_code == Some(503) {
+                warn!(
+                    "Service unavailable:\nCrawler: {:#?} \n{:#?}\n{:#?}",
+                    university,
+                    error.error,
+                    error.message.unwrap_or("<no message>".to_string())
+                );
+
+                let mut response = HttpResponse::build(StatusCode::SERVICE_UNAVAILABLE);
+                if let Some(retry_after) = error.retry_after {
+                    response.append_header(("Retry-After", retry_after.to_string()));
+                }
+                return response
+                .body(json!({"error": "Service Unavailable", "message": "The university is currently unavailable, try again later"}).to_string());
+            }
+
+            error!(
+                "External error:\nCrawler: {:#?} \n{:#?}\n{:#?}\n{:#?}",
+                university,
+                error.error,
+                error.fault,
+                error.message.unwrap_or("<no message>".to_string())
+            );
+
+            return HttpResponse::build(StatusCode::from_u16(502).unwrap())
+            .body(json!({"error": "Bad Gateway", "message": "An external service error occurred"}).to_string());
+        }
+    }
+}
@@ -1,82 +1,108 @@
 // External libraries
 use actix_web::{
     HttpResponse, Responder, get,
-    http::StatusCode,
     web::{Data, Path, Query},
 };
-use log::error;
-use redis::Client;
 use serde_json::json;
-use std::collections::HashMap;
 
 // Internal modules
-use crate::crawlers::store::get_university_crawler;
-use crate::models::error::ErrorFault;
+use super::errors::error_response;
+use crate::cache::store::Caches;
+use crate::scheduler::prewarm::Prewarmer;
+use crate::crawlers::store::CrawlerRegistry;
+use crate::models::query::TimetableQuery;
 
 #[get("/timetable/{university}/lessons")]
 pub async fn get_lessons(
     path: Path<String>,
-    query: Query<HashMap<String, String>>,
-    redis_client: Data<Client>,
+    query: Query<TimetableQuery>,
+    registry: Data<CrawlerRegistry>,
+    caches: Data<Caches>,
+    prewarmer: Data<Prewarmer>,
 ) -> impl Responder {
     // Extract the university name from the path and convert it to lowercase
     let university = path.into_inner().to_lowercase().trim().to_string();
 
     // Find crawler
-    let crawler = match get_university_crawler(&university) {
+    let crawler = match registry.get(&university) {
         Some(crawler) => crawler,
         None => {
             // Crawler not found -> 404 Not Found
             return HttpResponse::NotFound()
-                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}', you can make your proposal at https://github.com/jacopofilonzi/timetable", university)}).to_string());
+                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}', the supported ones are listed at /timetable, you can make your proposal at https://github.com/jacopofilonzi/timetable", university)}).to_string());
         }
     };
 
+    // Every course is cached on its own, the filters are applied to the merged timetable
+    let query = query.into_inner();
+    let filter = query.filter.clone();
+    let queries = match query.lesson_queries().and_then(|queries| filter.validate().map(|_| queries)) {
+        Ok(queries) => queries,
+        Err(error) => return error_response(&university, error),
+    };
+
     match crawler
-        .get_cached_lessons(university.clone(), query.into_inner(), redis_client)
+        .get_cached_timetable(university.clone(), queries.clone(), caches)
         .await
     {
         Ok(lessons) => {
-            // Return the courses as JSON
-            return HttpResponse::Ok().json(lessons);
+            // Count the request of every course for the pre-warming scheduler
+            prewarmer.record(&university, &queries);
+
+            // Return the lessons as JSON
+            let mut response = HttpResponse::Ok();
+            for header in lessons.status.headers() {
+                response.append_header(header);
+            }
+            return response.json(filter.apply(lessons.value));
         }
 
         Err(error) => {
-            // An error occurred while getting courses
-            match error.fault {
-                ErrorFault::User => {
-                    return HttpResponse::build(
-                        StatusCode::from_u16(error.http_code.unwrap_or(400)).unwrap(),
-                    )
-                    .body(json!({ "error": error.error, "message": error.message }).to_string());
-                }
+            // An error occurred while getting lessons
+            return error_response(&university, error);
+        }
+    };
+}
 
-                ErrorFault::Internal => {
-                    error!(
-                        "Internal error:\nCrawler: {:#?} \n{:#?}\n{:#?}\n{:#?}",
-                        university,
-                        error.error,
-                        error.fault,
-                        error.message.unwrap_or("<no message>".to_string())
-                    );
+#[get("/timetable/{university}/lessons/{id}")]
+pub async fn get_lesson(
+    path: Path<(String, String)>,
+    registry: Data<CrawlerRegistry>,
+    caches: Data<Caches>,
+) -> impl Responder {
+    let (university, id) = path.into_inner();
+
+    // Extract the university name from the path and convert it to lowercase
+    let university = university.to_lowercase().trim().to_string();
+
+    // Find crawler
+    let crawler = match registry.get(&university) {
+        Some(crawler) => crawler,
+        None => {
+            // Crawler not found -> 404 Not Found
+            return HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}', the supported ones are listed at /timetable, you can make your proposal at https://github.com/jacopofilonzi/timetable", university)}).to_string());
+        }
+    };
 
-                    return HttpResponse::build(StatusCode::from_u16(500).unwrap())
-                    .body(json!({"error": "Internal ServerError", "message":"An internal error occurred"}).to_string());
-                }
+    match crawler
+        .get_cached_lesson(university.clone(), id.clone(), caches)
+        .await
+    {
+        Ok(Some(lesson)) => {
+            // Return the lesson as JSON
+            return HttpResponse::Ok().json(lesson);
+        }
 
-                ErrorFault::External => {
-                    error!(
-                        "External error:\nCrawler: {:#?} \n{:#?}\n{:#?}\n{:#?}",
-                        university,
-                        error.error,
-                        error.fault,
-                        error.message.unwrap_or("<no message>".to_string())
-                    );
+        Ok(None) => {
+            // Lesson not cached (never crawled or expired) -> 404 Not Found
+            return HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No lesson found with id '{}', lessons are available once their timetable has been requested", id)}).to_string());
+        }
 
-                    return HttpResponse::build(StatusCode::from_u16(502).unwrap())
-                    .body(json!({"error": "Bad Gateway", "message": "An external service error occurred"}).to_string());
-                }
-            }
+        Err(error) => {
+            // An error occurred while reading the cache
+            return error_response(&university, error);
         }
     };
 }
@@ -1,9 +1,20 @@
 // External libraries
-use actix_web::{App, HttpServer, middleware::Logger, web::Data};
+use actix_web::{
+    App, HttpRequest, HttpResponse, HttpServer,
+    error::{InternalError, JsonPayloadError, QueryPayloadError},
+    middleware::Logger,
+    web::{Data, JsonConfig, QueryConfig, ServiceConfig},
+};
 use actix_files as fs;
+use serde_json::json;
 
 // Internal modules
+use crate::cache::store::Caches;
+use crate::crawlers::store::CrawlerRegistry;
+use crate::models::error::{Error, ErrorFault};
+use crate::profiles::store::{ProfileConfig, ProfileStore};
 use crate::redis_helper::connection_manager::RedisClient;
+use crate::scheduler::prewarm::{PrewarmConfig, Prewarmer};
 
 #[actix_web::main]
 pub async fn start_webserver(redis_client: RedisClient) -> std::io::Result<()> {
@@ -18,19 +29,36 @@ pub async fn start_webserver(redis_client: RedisClient) -> std::io::Result<()> {
 
     let logger_format = std::env::var("ACTIX_LOG_FORMAT").ok();
 
+    let redis_connection = redis_client
+        .connection_manager()
+        .await
+        .expect("Failed to create Redis connection manager");
+
+    let registry = Data::new(CrawlerRegistry::from_env());
+    let caches = Data::new(Caches::new(redis_connection.clone()));
+    let prewarmer = Data::new(Prewarmer::new(redis_connection.clone(), PrewarmConfig::from_env()));
+    let profiles = Data::new(ProfileStore::new(redis_connection, ProfileConfig::from_env()));
+
+    // Keep the most requested timetables warm in the background
+    actix_web::rt::spawn(Prewarmer::run(
+        prewarmer.clone(),
+        registry.clone(),
+        caches.clone(),
+    ));
 
     HttpServer::new(move || {
         App::new()
-            .app_data(Data::new(redis_client.client.clone()).clone()) // Share Redis client across handlers
+            .app_data(registry.clone()) // Share crawlers across handlers
+            .app_data(caches.clone()) // Share caches across handlers
+            .app_data(prewarmer.clone()) // Share the popularity tracker across handlers
+            .app_data(profiles.clone()) // Share the saved profiles across handlers
             .wrap(
                 match &logger_format {
                     Some(format) => Logger::new(format), // Use custom log format if provided
                     None => Logger::default(), // Default logger
                 }
             ) // Enable logging middleware
-            .service(super::courses::get_courses)
-            .service(super::lessons::get_lessons)
-            .service(super::lessons_ics::get_ics_lessons)
+            .configure(configure)
             .service(
                 match cfg!(debug_assertions) { // Check if in debug mode
                     // Serve static files from the public directory in debug mode
@@ -51,3 +79,46 @@ pub async fn start_webserver(redis_client: RedisClient) -> std::io::Result<()> {
     .run()
     .await
 }
+
+/// Registers the API endpoints, the crawler registry, caches, popularity tracker and profile store have to be shared by the app
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.app_data(QueryConfig::default().error_handler(query_error_handler)) // Malformed query parameters -> 400 Bad Request
+        .app_data(JsonConfig::default().error_handler(json_error_handler)) // Malformed JSON bodies -> 400 Bad Request
+        .service(super::status::get_status) // Before get_university, which would match it too
+        .service(super::universities::get_universities)
+        .service(super::universities::get_university)
+        .service(super::courses::get_courses)
+        .service(super::lessons::get_lessons)
+        .service(super::lessons::get_lesson)
+        .service(super::lessons_ics::get_ics_lessons)
+        .service(super::profiles::create_profile)
+        .service(super::profiles::get_profile_lessons)
+        .service(super::profiles::get_profile_ics);
+}
+
+/// Turns a query deserialization failure into the same JSON body returned by the handlers
+fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let error = Error {
+        error: "Bad request".into(),
+        http_code: Some(400),
+        message: Some(match &err {
+            QueryPayloadError::Deserialize(inner) => inner.to_string(),
+            _ => err.to_string(),
+        }),
+        fault: ErrorFault::User,
+        retry_after: None,
+    };
+
+    let response = HttpResponse::BadRequest()
+        .body(json!({ "error": error.error, "message": error.message }).to_string());
+
+    InternalError::from_response(err, response).into()
+}
+
+/// Same as `query_error_handler` for JSON bodies
+fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let response = HttpResponse::BadRequest()
+        .body(json!({ "error": "Bad request", "message": err.to_string() }).to_string());
+
+    InternalError::from_response(err, response).into()
+}
@@ -1,4 +1,8 @@
 pub mod main;
 pub mod courses;
+pub mod errors;
 pub mod lessons;
-pub mod lessons_ics;
\ No newline at end of file
+pub mod lessons_ics;
+pub mod profiles;
+pub mod status;
+pub mod universities;
\ No newline at end of file
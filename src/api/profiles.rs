@@ -0,0 +1,155 @@
+// External libraries
+use actix_web::{
+    HttpResponse, Responder, get,
+    http::StatusCode,
+    post,
+    web::{Data, Json, Path},
+};
+use serde_json::json;
+
+// Internal modules
+use super::errors::error_response;
+use crate::cache::{main::Cached, store::Caches};
+use crate::crawlers::store::CrawlerRegistry;
+use crate::ics::calendar::lessons_calendar;
+use crate::models::{lesson::Lesson, profile::Profile, university::Capability};
+use crate::profiles::store::ProfileStore;
+use crate::scheduler::prewarm::Prewarmer;
+
+#[post("/timetable/profiles")]
+pub async fn create_profile(
+    profile: Json<Profile>,
+    registry: Data<CrawlerRegistry>,
+    profiles: Data<ProfileStore>,
+) -> impl Responder {
+    let mut profile = profile.into_inner();
+    profile.university = profile.university.to_lowercase().trim().to_string();
+
+    // Only profiles that can be rendered are saved
+    let crawler = match registry.get(&profile.university) {
+        Some(crawler) => crawler,
+        None => {
+            return HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}', the supported ones are listed at /timetable", profile.university)}).to_string());
+        }
+    };
+    let metadata = crawler.metadata();
+    if let Err(error) = profile
+        .validate(&metadata)
+        .and_then(|_| metadata.check_parameters(Capability::Lessons, &profile.timetable.extra))
+    {
+        return error_response(&profile.university, error);
+    }
+
+    match profiles.save(&profile).await {
+        Ok(token) => {
+            let ics = format!("/timetable/p/{}.ics", token);
+            return HttpResponse::Created()
+                .append_header(("Location", ics.clone()))
+                .json(json!({ "token": token, "ics": ics, "json": format!("/timetable/p/{}.json", token) }));
+        }
+
+        Err(error) => {
+            return error_response(&profile.university, error);
+        }
+    };
+}
+
+#[get("/timetable/p/{token:[0-9A-Za-z]+}.json")]
+pub async fn get_profile_lessons(
+    path: Path<String>,
+    registry: Data<CrawlerRegistry>,
+    caches: Data<Caches>,
+    prewarmer: Data<Prewarmer>,
+    profiles: Data<ProfileStore>,
+) -> impl Responder {
+    match render_profile(&path.into_inner(), registry, caches, prewarmer, profiles).await {
+        Ok((_, lessons)) => {
+            let mut response = HttpResponse::Ok();
+            for header in lessons.status.headers() {
+                response.append_header(header);
+            }
+            return response.json(lessons.value);
+        }
+
+        Err(response) => {
+            return response;
+        }
+    };
+}
+
+#[get("/timetable/p/{token:[0-9A-Za-z]+}.ics")]
+pub async fn get_profile_ics(
+    path: Path<String>,
+    registry: Data<CrawlerRegistry>,
+    caches: Data<Caches>,
+    prewarmer: Data<Prewarmer>,
+    profiles: Data<ProfileStore>,
+) -> impl Responder {
+    match render_profile(&path.into_inner(), registry, caches, prewarmer, profiles).await {
+        Ok((profile, lessons)) => {
+            let mut response = HttpResponse::build(StatusCode::OK);
+            for header in lessons.status.headers() {
+                response.append_header(header);
+            }
+            return response
+                .append_header(("Content-Disposition", "attachment; filename=timetable.ics"))
+                .append_header(("Content-Type", "text/calendar"))
+                .body(lessons_calendar(
+                    &lessons.value,
+                    &profile.university,
+                    &profile.reminders,
+                ));
+        }
+
+        Err(response) => {
+            return response;
+        }
+    };
+}
+
+/// Loads the profile and its filtered lessons through the same cache as the lessons endpoints,
+/// failures are returned as the response to send
+async fn render_profile(
+    token: &str,
+    registry: Data<CrawlerRegistry>,
+    caches: Data<Caches>,
+    prewarmer: Data<Prewarmer>,
+    profiles: Data<ProfileStore>,
+) -> Result<(Profile, Cached<Vec<Lesson>>), HttpResponse> {
+    let profile = match profiles.get(token).await {
+        Ok(Some(profile)) => profile,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No profile found for token '{}'", token)}).to_string()));
+        }
+        Err(error) => return Err(error_response("profiles", error)),
+    };
+
+    let crawler = match registry.get(&profile.university) {
+        Some(crawler) => crawler,
+        None => {
+            // The university was disabled after the profile was saved
+            return Err(HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}'", profile.university)}).to_string()));
+        }
+    };
+
+    let queries = profile
+        .validate(&crawler.metadata())
+        .map_err(|error| error_response(&profile.university, error))?;
+
+    let lessons = crawler
+        .get_cached_timetable(profile.university.clone(), queries.clone(), caches)
+        .await
+        .map_err(|error| error_response(&profile.university, error))?;
+
+    // Count the request of every course for the pre-warming scheduler
+    prewarmer.record(&profile.university, &queries);
+
+    let lessons = Cached {
+        value: profile.timetable.filter.apply(lessons.value),
+        status: lessons.status,
+    };
+    return Ok((profile, lessons));
+}
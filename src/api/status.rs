@@ -0,0 +1,19 @@
+// External libraries
+use actix_web::{HttpResponse, Responder, get, web::Data};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+// Internal modules
+use crate::crawlers::breaker::BreakerStatus;
+use crate::crawlers::store::CrawlerRegistry;
+
+#[get("/timetable/status")]
+pub async fn get_status(registry: Data<CrawlerRegistry>) -> impl Responder {
+    // Return the circuit breaker state of every enabled crawler as JSON
+    let crawlers: BTreeMap<&str, BreakerStatus> = registry
+        .breakers()
+        .map(|(id, breaker)| (*id, breaker.status()))
+        .collect();
+
+    return HttpResponse::Ok().body(json!({ "crawlers": crawlers }).to_string());
+}
@@ -0,0 +1,36 @@
+// External libraries
+use actix_web::{
+    HttpResponse, Responder, get,
+    web::{Data, Path},
+};
+use serde_json::json;
+
+// Internal modules
+use crate::crawlers::store::CrawlerRegistry;
+use crate::models::university::University;
+
+#[get("/timetable")]
+pub async fn get_universities(registry: Data<CrawlerRegistry>) -> impl Responder {
+    // Return the metadata of every enabled crawler as JSON
+    let universities: Vec<University> = registry.all().map(|crawler| crawler.metadata()).collect();
+
+    return HttpResponse::Ok().json(universities);
+}
+
+#[get("/timetable/{university}")]
+pub async fn get_university(path: Path<String>, registry: Data<CrawlerRegistry>) -> impl Responder {
+    // Extract the university name from the path and convert it to lowercase
+    let university = path.into_inner().to_lowercase().trim().to_string();
+
+    match registry.get(&university) {
+        Some(crawler) => {
+            // Return the crawler metadata as JSON
+            return HttpResponse::Ok().json(crawler.metadata());
+        }
+        None => {
+            // Crawler not found -> 404 Not Found
+            return HttpResponse::NotFound()
+                .body(json!({"error": "Not Found", "message": format!("No crawler found for university '{}', the supported ones are listed at /timetable, you can make your proposal at https://github.com/jacopofilonzi/timetable", university)}).to_string());
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// External libraries
+use chrono::Utc;
+use futures_util::future::{Either, select};
+use log::warn;
+use redis::{AsyncCommands, RedisResult, Script, aio::ConnectionManager};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+
+// Internal modules
+use crate::models::error::Error;
+
+/// How long the crawl lock of a key outlives its last extension (seconds), in case the replica holding it dies
+const LOCK_TTL: u64 = 60;
+/// How often the replica crawling a key pushes back the expiration of its lock
+const LOCK_EXTEND_INTERVAL: Duration = Duration::from_secs(LOCK_TTL / 3);
+/// How often other replicas check whether the lock has been released
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Outcome of a crawl shared with the callers waiting for it, `None` while still running
+type Outcome = Option<Result<(), Error>>;
+
+/// In-process deduplication of concurrent crawls of the same key
+#[derive(Default)]
+pub struct SingleFlight {
+    inflight: Mutex<HashMap<String, watch::Receiver<Outcome>>>,
+}
+
+pub enum Flight<'a> {
+    /// First caller, it has to crawl and report the outcome
+    Leader(FlightGuard<'a>),
+    /// Another caller is already crawling the key
+    Follower(watch::Receiver<Outcome>),
+}
+
+/// Held by the leader for the whole crawl, the key is released when dropped
+pub struct FlightGuard<'a> {
+    flights: &'a SingleFlight,
+    key: String,
+    sender: watch::Sender<Outcome>,
+}
+
+impl SingleFlight {
+    /// Joins the crawl of the key, becoming its leader if nobody is crawling it yet
+    pub fn join(&self, key: &str) -> Flight<'_> {
+        let mut inflight = self.inflight.lock().unwrap();
+
+        if let Some(receiver) = inflight.get(key) {
+            return Flight::Follower(receiver.clone());
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        inflight.insert(key.to_string(), receiver);
+
+        Flight::Leader(FlightGuard {
+            flights: self,
+            key: key.to_string(),
+            sender,
+        })
+    }
+
+    /// Waits for the leader, `None` if it went away without reporting
+    pub async fn wait(mut receiver: watch::Receiver<Outcome>) -> Outcome {
+        match receiver.wait_for(|outcome| outcome.is_some()).await {
+            Ok(outcome) => outcome.clone(),
+            Err(_) => None,
+        }
+    }
+}
+
+impl FlightGuard<'_> {
+    /// Reports the outcome to the followers
+    pub fn finish(self, outcome: Result<(), Error>) {
+        let _ = self.sender.send(Some(outcome));
+    }
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        self.flights.inflight.lock().unwrap().remove(&self.key);
+    }
+}
+
+// ================ Cross replica lock =================
+// The leader of each replica also takes a Redis lock, so only one replica crawls a key at a time
+
+/// Deletes KEYS[1] if it still holds the token ARGV[1]
+const RELEASE_SCRIPT: &str =
+    r#"if redis.call("GET", KEYS[1]) == ARGV[1] then return redis.call("DEL", KEYS[1]) else return 0 end"#;
+/// Pushes the expiration of KEYS[1] to ARGV[2] seconds if it still holds the token ARGV[1]
+const EXTEND_SCRIPT: &str =
+    r#"if redis.call("GET", KEYS[1]) == ARGV[1] then return redis.call("EXPIRE", KEYS[1], ARGV[2]) else return 0 end"#;
+
+/// Crawl lock held by this replica, released when dropped even if the crawl is cancelled halfway
+pub struct LockGuard {
+    connection: ConnectionManager,
+    key: String,
+    /// `None` once released
+    token: Option<String>,
+}
+
+/// Tries to take the crawl lock of the key || key -> `lock:<cache_key>`.
+/// Returns `None` if another replica holds it.
+/// If Redis is unreachable the lock is considered taken by us.
+pub async fn acquire_lock(connection: &ConnectionManager, key: &str) -> Option<LockGuard> {
+    let mut connection = connection.clone();
+    let token = format!(
+        "{}-{}-{}",
+        std::env::var("HOSTNAME").unwrap_or_default(),
+        std::process::id(),
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+
+    let result: RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(format!("lock:{}", key))
+        .arg(&token)
+        .arg("NX")
+        .arg("EX")
+        .arg(LOCK_TTL)
+        .query_async(&mut connection)
+        .await;
+
+    let acquired = match result {
+        Ok(acquired) => acquired.is_some(),
+        Err(err) => {
+            warn!("Failed to lock '{}' on Redis: {}", key, err);
+            true
+        }
+    };
+
+    acquired.then(|| LockGuard {
+        connection,
+        key: key.to_string(),
+        token: Some(token),
+    })
+}
+
+impl LockGuard {
+    /// Runs the crawl while holding the lock, pushing its expiration back until the crawl is over.
+    /// A crawl can outlast `LOCK_TTL` with its retries and queueing, the lock must not expire under it.
+    pub async fn hold<Fut: Future>(&self, crawl: Fut) -> Fut::Output {
+        let keep_alive = async {
+            loop {
+                tokio::time::sleep(LOCK_EXTEND_INTERVAL).await;
+                self.extend().await;
+            }
+        };
+
+        let (crawl, keep_alive) = (pin!(crawl), pin!(keep_alive));
+        match select(crawl, keep_alive).await {
+            Either::Left((output, _)) => output,
+            Either::Right((never, _)) => never,
+        }
+    }
+
+    /// Releases the lock if it is still ours
+    pub async fn release(mut self) {
+        if let Some(token) = self.token.take() {
+            release_lock(&self.connection, &self.key, &token).await;
+        }
+    }
+
+    async fn extend(&self) {
+        let Some(token) = &self.token else {
+            return;
+        };
+
+        let mut connection = self.connection.clone();
+        let result: RedisResult<i32> = Script::new(EXTEND_SCRIPT)
+            .key(format!("lock:{}", self.key))
+            .arg(token)
+            .arg(LOCK_TTL)
+            .invoke_async(&mut connection)
+            .await;
+
+        match result {
+            Ok(0) => warn!("Lost the lock of '{}' while crawling", self.key),
+            Ok(_) => {}
+            Err(err) => warn!("Failed to extend the lock of '{}' on Redis: {}", self.key, err),
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    // The crawl was cancelled, release in the background instead of leaving the lock until it expires
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            let connection = self.connection.clone();
+            let key = std::mem::take(&mut self.key);
+            actix_web::rt::spawn(async move { release_lock(&connection, &key, &token).await });
+        }
+    }
+}
+
+async fn release_lock(connection: &ConnectionManager, key: &str, token: &str) {
+    let mut connection = connection.clone();
+
+    let result: RedisResult<i32> = Script::new(RELEASE_SCRIPT)
+        .key(format!("lock:{}", key))
+        .arg(token)
+        .invoke_async(&mut connection)
+        .await;
+
+    if let Err(err) = result {
+        warn!("Failed to unlock '{}' on Redis: {}", key, err);
+    }
+}
+
+/// Waits until the replica holding the lock releases it or the lock expires.
+/// The holder keeps the lock alive only while crawling, so the wait ends once its crawl does.
+pub async fn wait_for_lock(connection: &ConnectionManager, key: &str) {
+    let mut connection = connection.clone();
+
+    loop {
+        let locked: RedisResult<bool> = connection.exists(format!("lock:{}", key)).await;
+        if !matches!(locked, Ok(true)) {
+            return;
+        }
+
+        tokio::time::sleep(LOCK_POLL_INTERVAL).await;
+    }
+}
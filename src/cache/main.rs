@@ -0,0 +1,413 @@
+// External libraries
+use chrono::Utc;
+use log::warn;
+use redis::{AsyncCommands, RedisResult, aio::ConnectionManager};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::json;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+// Internal modules
+use super::flight::{self, Flight, SingleFlight};
+use crate::models::error::{Error, ErrorFault};
+
+/// How long entries are considered fresh and how long they can be served once stale (seconds).
+///
+/// - `ttl`: the entry is served as is
+/// - `stale_while_revalidate`: past the TTL the entry is served immediately and refreshed in the background
+/// - `stale_if_error`: past the TTL the entry is refreshed synchronously and served only if upstream fails
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    pub ttl: u64,
+    pub stale_while_revalidate: u64,
+    pub stale_if_error: u64,
+}
+
+impl CachePolicy {
+    /// Overrides the defaults with `CACHE_TTL_<NAMESPACE>`, `CACHE_SWR_<NAMESPACE>` and
+    /// `CACHE_STALE_IF_ERROR_<NAMESPACE>` environment variables
+    fn from_env(namespace: &str, defaults: CachePolicy) -> Self {
+        let read = |name: &str, default: u64| {
+            std::env::var(format!("CACHE_{}_{}", name, namespace.to_uppercase()))
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            ttl: read("TTL", defaults.ttl),
+            stale_while_revalidate: read("SWR", defaults.stale_while_revalidate),
+            stale_if_error: read("STALE_IF_ERROR", defaults.stale_if_error),
+        }
+    }
+
+    /// How long Redis keeps the entry around
+    fn retention(&self) -> u64 {
+        self.ttl + self.stale_while_revalidate.max(self.stale_if_error)
+    }
+}
+
+/// Where a served value came from, ordered from the freshest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheStatus {
+    /// Fresh cached value
+    Hit,
+    /// Just crawled
+    Miss,
+    /// Stale cached value, a refresh is running in the background
+    Stale,
+    /// Stale cached value served because upstream failed
+    StaleIfError,
+}
+
+impl CacheStatus {
+    /// Headers describing the status to the client
+    pub fn headers(&self) -> Vec<(&'static str, &'static str)> {
+        match self {
+            CacheStatus::Hit => vec![("X-Cache", "HIT")],
+            CacheStatus::Miss => vec![("X-Cache", "MISS")],
+            CacheStatus::Stale => vec![
+                ("X-Cache", "STALE"),
+                ("Warning", "110 - \"Response is Stale\""),
+            ],
+            CacheStatus::StaleIfError => vec![
+                ("X-Cache", "STALE"),
+                ("Warning", "111 - \"Revalidation Failed\""),
+            ],
+        }
+    }
+}
+
+/// A value returned by the cache along with its status
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    pub status: CacheStatus,
+}
+
+/// Stored shape of every entry
+#[derive(Deserialize)]
+struct Entry<T> {
+    stored_at: i64,
+    value: T,
+}
+
+/// Redis backed cache for a single kind of resource.
+/// Every entry is stored as JSON under `<namespace>:v<version>:<key>` and expires once it can't be served anymore,
+/// bumping the schema version leaves the old entries to expire on their own.
+pub struct Cache<T> {
+    connection: ConnectionManager,
+    namespace: String,
+    version: u32,
+    policy: CachePolicy,
+    /// Crawls in progress, shared by every clone
+    flights: Arc<SingleFlight>,
+    _resource: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Cache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            connection: self.connection.clone(),
+            namespace: self.namespace.clone(),
+            version: self.version,
+            policy: self.policy,
+            flights: self.flights.clone(),
+            _resource: PhantomData,
+        }
+    }
+}
+
+impl<T> Cache<T>
+where
+    T: Serialize + DeserializeOwned + 'static,
+{
+    /// Creates a cache for the given namespace and schema version of the stored resource.
+    /// The policy can be overridden through environment variables, see `CachePolicy`.
+    pub fn new(
+        connection: ConnectionManager,
+        namespace: &str,
+        version: u32,
+        default_policy: CachePolicy,
+    ) -> Self {
+        Self {
+            connection,
+            namespace: namespace.to_string(),
+            version,
+            policy: CachePolicy::from_env(namespace, default_policy),
+            flights: Arc::new(SingleFlight::default()),
+            _resource: PhantomData,
+        }
+    }
+
+    /// Hashes any serializable value into a key fragment
+    pub fn hash_key<K: Serialize>(value: &K) -> String {
+        format!("{:x}", md5::compute(serde_json::to_string(value).unwrap()))
+    }
+
+    /// Returns the cached value regardless of its age, `None` on miss or if Redis is unreachable
+    pub async fn get(&self, key: &str) -> Option<T> {
+        self.lookup(key).await.map(|(value, _)| value)
+    }
+
+    /// Stores the value, failures are only logged
+    pub async fn set(&self, key: &str, value: &T) {
+        self.set_many(std::iter::once((key, value))).await;
+    }
+
+    /// Stores many values at once in a single round trip
+    pub async fn set_many<'a, I>(&self, entries: I)
+    where
+        T: 'a,
+        I: IntoIterator<Item = (&'a str, &'a T)>,
+    {
+        let mut connection = self.connection.clone();
+        let stored_at = Utc::now().timestamp();
+
+        let mut pipe = redis::pipe();
+        for (key, value) in entries {
+            pipe.set_ex(
+                self.full_key(key),
+                json!({ "stored_at": stored_at, "value": value }).to_string(),
+                self.policy.retention(),
+            )
+            .ignore();
+        }
+
+        let result: RedisResult<()> = pipe.query_async(&mut connection).await;
+        if let Err(err) = result {
+            warn!("Failed to write to the '{}' cache: {}", self.namespace, err);
+        }
+    }
+
+    /// Returns the cached value or fetches and caches it, following the cache policy.
+    /// Concurrent misses of the same key are coalesced into a single fetch.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &str, fetch: F) -> Result<Cached<T>, Error>
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = Result<T, Error>> + 'static,
+    {
+        let cached = self.lookup(key).await;
+
+        match cached {
+            Some((value, age)) if age < self.policy.ttl => {
+                return Ok(Cached {
+                    value,
+                    status: CacheStatus::Hit,
+                });
+            }
+
+            Some((value, age)) if age < self.policy.ttl + self.policy.stale_while_revalidate => {
+                // Serve the stale copy right away and refresh it in the background
+                let cache = self.clone();
+                let key = key.to_string();
+                actix_web::rt::spawn(async move {
+                    if let Err(err) = cache.refresh(&key, fetch).await {
+                        warn!(
+                            "Background refresh of '{}' failed: {} {}",
+                            cache.full_key(&key),
+                            err.error,
+                            err.message.unwrap_or_default()
+                        );
+                    }
+                });
+
+                return Ok(Cached {
+                    value,
+                    status: CacheStatus::Stale,
+                });
+            }
+
+            stale => match self.refresh(key, fetch).await {
+                Ok(value) => return Ok(value),
+
+                Err(err) => match stale {
+                    // Upstream is failing, fall back to the stale copy if it is still allowed
+                    Some((value, age))
+                        if err.fault == ErrorFault::External
+                            && age < self.policy.ttl + self.policy.stale_if_error =>
+                    {
+                        warn!(
+                            "Serving stale '{}' after upstream failure: {} {}",
+                            self.full_key(key),
+                            err.error,
+                            err.message.unwrap_or_default()
+                        );
+
+                        return Ok(Cached {
+                            value,
+                            status: CacheStatus::StaleIfError,
+                        });
+                    }
+                    _ => return Err(err),
+                },
+            },
+        }
+    }
+
+    /// Fetches the value again if it is missing or expires within `margin` seconds.
+    /// Returns whether a fetch happened.
+    pub async fn prewarm<F, Fut>(&self, key: &str, margin: u64, fetch: F) -> Result<bool, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        if let Some((_, age)) = self.lookup(key).await
+            && age + margin < self.policy.ttl
+        {
+            return Ok(false);
+        }
+
+        self.refresh(key, fetch).await?;
+        Ok(true)
+    }
+
+    /// Fetches and stores the value, making sure a single fetch per key runs across callers and replicas.
+    /// Callers that waited for someone else's fetch read its result back from the cache.
+    async fn refresh<F, Fut>(&self, key: &str, fetch: F) -> Result<Cached<T>, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let full_key = self.full_key(key);
+
+        let guard = match self.flights.join(&full_key) {
+            Flight::Follower(receiver) => {
+                match SingleFlight::wait(receiver).await {
+                    Some(Err(err)) => return Err(err),
+                    Some(Ok(())) => {
+                        if let Some((value, _)) = self.lookup(key).await {
+                            return Ok(Cached {
+                                value,
+                                status: CacheStatus::Miss,
+                            });
+                        }
+                    }
+                    None => {}
+                }
+
+                // The leader went away or its result can't be read back, fetch on our own
+                let value = fetch().await?;
+                self.set(key, &value).await;
+                return Ok(Cached {
+                    value,
+                    status: CacheStatus::Miss,
+                });
+            }
+
+            Flight::Leader(guard) => guard,
+        };
+
+        // Another replica is already fetching, wait for it and read its result back
+        let Some(lock) = flight::acquire_lock(&self.connection, &full_key).await else {
+            flight::wait_for_lock(&self.connection, &full_key).await;
+
+            if let Some((value, age)) = self.lookup(key).await
+                && age < self.policy.ttl
+            {
+                guard.finish(Ok(()));
+                return Ok(Cached {
+                    value,
+                    status: CacheStatus::Miss,
+                });
+            }
+
+            return self.fetch_and_store(key, fetch, guard).await;
+        };
+
+        // Dropping the lock releases it as well, should this request be cancelled mid-crawl
+        let result = lock.hold(self.fetch_and_store(key, fetch, guard)).await;
+        lock.release().await;
+
+        result
+    }
+
+    async fn fetch_and_store<F, Fut>(
+        &self,
+        key: &str,
+        fetch: F,
+        guard: flight::FlightGuard<'_>,
+    ) -> Result<Cached<T>, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        match fetch().await {
+            Ok(value) => {
+                self.set(key, &value).await;
+                guard.finish(Ok(()));
+                Ok(Cached {
+                    value,
+                    status: CacheStatus::Miss,
+                })
+            }
+            Err(err) => {
+                guard.finish(Err(err.clone()));
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the cached value and its age in seconds.
+    /// Entries that can't be decoded anymore are evicted and reported as a miss.
+    async fn lookup(&self, key: &str) -> Option<(T, u64)> {
+        let mut connection = self.connection.clone();
+
+        let cache_result: RedisResult<Option<String>> =
+            connection.get(self.full_key(key)).await;
+
+        match cache_result {
+            Ok(Some(value)) => match serde_json::from_str::<Entry<T>>(&value) {
+                Ok(entry) => {
+                    let age = (Utc::now().timestamp() - entry.stored_at).max(0) as u64;
+                    Some((entry.value, age))
+                }
+                Err(err) => {
+                    self.evict_corrupted(key, err).await;
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(err) => {
+                warn!(
+                    "Failed to read '{}' from Redis: {}",
+                    self.full_key(key),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Removes an undecodable entry and counts it || counter -> `cache:decode_failures:<namespace>`
+    async fn evict_corrupted(&self, key: &str, err: serde_json::Error) {
+        warn!(
+            "Evicting corrupted cache entry '{}' (schema v{}): {}",
+            self.full_key(key),
+            self.version,
+            err
+        );
+
+        let mut connection = self.connection.clone();
+        let result: RedisResult<()> = redis::pipe()
+            .del(self.full_key(key))
+            .ignore()
+            .incr(format!("cache:decode_failures:{}", self.namespace), 1)
+            .ignore()
+            .query_async(&mut connection)
+            .await;
+
+        if let Err(err) = result {
+            warn!(
+                "Failed to evict '{}' from Redis: {}",
+                self.full_key(key),
+                err
+            );
+        }
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        format!("{}:v{}:{}", self.namespace, self.version, key)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod flight;
+pub mod main;
+pub mod store;
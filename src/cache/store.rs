@@ -0,0 +1,55 @@
+// External libraries
+use redis::aio::ConnectionManager;
+
+// Internal modules
+use super::main::{Cache, CachePolicy};
+use crate::models::{course::Course, lesson::Lesson};
+
+const DAY: u64 = 60 * 60 * 24;
+
+const LESSONS_POLICY: CachePolicy = CachePolicy {
+    ttl: 3 * DAY,
+    stale_while_revalidate: DAY,
+    stale_if_error: 14 * DAY,
+};
+
+const COURSES_POLICY: CachePolicy = CachePolicy {
+    ttl: 90 * DAY,
+    stale_while_revalidate: 7 * DAY,
+    stale_if_error: 90 * DAY,
+};
+
+/// Caches for every resource served by the API, shared across handlers
+pub struct Caches {
+    /// Lessons lists || key -> `lessons:v<version>:<university>:<from>:<to>:<query_hash>`
+    pub lessons: Cache<Vec<Lesson>>,
+    /// Single lessons indexed by id || key -> `lesson:v<version>:<university>:<id>`
+    pub lesson: Cache<Lesson>,
+    /// Courses lists || key -> `courses:v<version>:<university>:<query_hash>`
+    pub courses: Cache<Vec<Course>>,
+}
+
+impl Caches {
+    pub fn new(connection: ConnectionManager) -> Self {
+        Self {
+            lessons: Cache::new(
+                connection.clone(),
+                "lessons",
+                Lesson::SCHEMA_VERSION,
+                LESSONS_POLICY,
+            ),
+            lesson: Cache::new(
+                connection.clone(),
+                "lesson",
+                Lesson::SCHEMA_VERSION,
+                LESSONS_POLICY,
+            ),
+            courses: Cache::new(
+                connection,
+                "courses",
+                Course::SCHEMA_VERSION,
+                COURSES_POLICY,
+            ),
+        }
+    }
+}
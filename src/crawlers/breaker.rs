@@ -0,0 +1,247 @@
+// External libraries
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Internal modules
+use super::main::UniversityCrawler;
+use crate::models::{
+    course::Course,
+    date_range::DateRange,
+    error::{Error, ErrorFault},
+    lesson::Lesson,
+    query::{CourseQuery, LessonQuery},
+    university::University,
+};
+
+/// Circuit breaker settings.
+///
+/// Every setting is read from `BREAKER_<SETTING>_<UNIVERSITY>`, then `BREAKER_<SETTING>`, then the default:
+/// - `THRESHOLD`: consecutive upstream failures that open the circuit, `0` disables the breaker (default 5)
+/// - `COOLDOWN`: seconds the circuit stays open before letting a probe request through (default 60)
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerConfig {
+    pub threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl BreakerConfig {
+    pub fn from_env(university: &str) -> Self {
+        let read = |name: &str| -> Option<u64> {
+            std::env::var(format!("BREAKER_{}_{}", name, university.to_uppercase()))
+                .or_else(|_| std::env::var(format!("BREAKER_{}", name)))
+                .ok()
+                .and_then(|s| s.parse().ok())
+        };
+
+        Self {
+            threshold: read("THRESHOLD").map(|n| n as u32).unwrap_or(5),
+            cooldown: Duration::from_secs(read("COOLDOWN").unwrap_or(60)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests reach the university
+    Closed,
+    /// The university is failing, requests are rejected right away
+    Open,
+    /// The cooldown is over, a single probe request is checking whether the university is back
+    HalfOpen,
+}
+
+/// Snapshot of a breaker, exposed on the status endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    /// Seconds before the next probe, only while open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+struct Circuit {
+    state: BreakerState,
+    consecutive_failures: u32,
+    /// When the circuit opened or the probe started
+    since: Instant,
+}
+
+/// Stops calling a university after too many consecutive failures
+pub struct CircuitBreaker {
+    university: &'static str,
+    config: BreakerConfig,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(university: &'static str, config: BreakerConfig) -> Self {
+        Self {
+            university,
+            config,
+            circuit: Mutex::new(Circuit {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let circuit = self.circuit.lock().unwrap();
+
+        BreakerStatus {
+            state: circuit.state,
+            consecutive_failures: circuit.consecutive_failures,
+            retry_after: match circuit.state {
+                BreakerState::Open => Some(self.remaining_cooldown(circuit.since)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Runs the call if the circuit allows it and records its outcome
+    pub async fn call<T, Fut>(&self, call: Fut) -> Result<T, Error>
+    where
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.allow()?;
+
+        let result = call.await;
+        match &result {
+            Ok(_) => self.on_success(),
+            // Only the university going down counts
+            Err(err) if err.fault == ErrorFault::External && err.http_code != Some(503) => {
+                self.on_failure()
+            }
+            // Our own rate limiting kept the request from the university, the next one probes it instead
+            Err(err) if err.fault == ErrorFault::External => self.on_inconclusive(),
+            // The university answered, the request itself was wrong
+            Err(_) => self.on_answer(),
+        }
+
+        result
+    }
+
+    fn allow(&self) -> Result<(), Error> {
+        if self.config.threshold == 0 {
+            return Ok(());
+        }
+
+        let mut circuit = self.circuit.lock().unwrap();
+
+        match circuit.state {
+            BreakerState::Closed => return Ok(()),
+
+            // Let a single probe through once the cooldown is over, a new one if the last probe never reported back
+            _ if circuit.since.elapsed() >= self.config.cooldown => {
+                info!("Circuit of {} half-open, probing the university", self.university);
+                circuit.state = BreakerState::HalfOpen;
+                circuit.since = Instant::now();
+                return Ok(());
+            }
+
+            _ => {
+                return Err(Error {
+                    error: format!("{} is currently unreachable", self.university),
+                    http_code: Some(503),
+                    message: Some(format!(
+                        "Circuit open after {} consecutive failures",
+                        circuit.consecutive_failures
+                    )),
+                    fault: ErrorFault::External,
+                    retry_after: Some(self.remaining_cooldown(circuit.since).max(1)),
+                });
+            }
+        }
+    }
+
+    fn on_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+
+        if circuit.state != BreakerState::Closed {
+            info!("Circuit of {} closed, the university is back", self.university);
+        }
+        circuit.state = BreakerState::Closed;
+        circuit.consecutive_failures = 0;
+    }
+
+    /// Closes the circuit if it was probing, failures counted so far stand otherwise
+    fn on_answer(&self) {
+        if self.circuit.lock().unwrap().state == BreakerState::HalfOpen {
+            self.on_success();
+        }
+    }
+
+    /// Lets the next request probe right away if the probe didn't reach the university
+    fn on_inconclusive(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+
+        if circuit.state == BreakerState::HalfOpen {
+            circuit.state = BreakerState::Open;
+            circuit.since = Instant::now().checked_sub(self.config.cooldown).unwrap_or(circuit.since);
+        }
+    }
+
+    fn on_failure(&self) {
+        if self.config.threshold == 0 {
+            return;
+        }
+
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures += 1;
+
+        let trip = match circuit.state {
+            BreakerState::Closed => circuit.consecutive_failures >= self.config.threshold,
+            // The probe failed, keep the circuit open for another cooldown
+            _ => true,
+        };
+
+        if trip {
+            if circuit.state == BreakerState::Closed {
+                warn!(
+                    "Circuit of {} opened after {} consecutive failures",
+                    self.university, circuit.consecutive_failures
+                );
+            }
+            circuit.state = BreakerState::Open;
+            circuit.since = Instant::now();
+        }
+    }
+
+    fn remaining_cooldown(&self, since: Instant) -> u64 {
+        self.config.cooldown.saturating_sub(since.elapsed()).as_secs()
+    }
+}
+
+/// Crawler calling the wrapped one through its circuit breaker
+pub struct GuardedCrawler {
+    crawler: Arc<dyn UniversityCrawler>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl GuardedCrawler {
+    pub fn new(crawler: Arc<dyn UniversityCrawler>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { crawler, breaker }
+    }
+}
+
+#[async_trait]
+impl UniversityCrawler for GuardedCrawler {
+    fn metadata(&self) -> University {
+        self.crawler.metadata()
+    }
+
+    async fn get_lessons(&self, query: LessonQuery, range: DateRange) -> Result<Vec<Lesson>, Error> {
+        self.breaker.call(self.crawler.get_lessons(query, range)).await
+    }
+
+    async fn get_courses(&self, query: CourseQuery) -> Result<Vec<Course>, Error> {
+        self.breaker.call(self.crawler.get_courses(query)).await
+    }
+}
@@ -0,0 +1,309 @@
+// Minimal, tolerant HTML parser used by the crawlers to walk upstream pages as a tree.
+// Attributes can come in any order and with any quoting, entities are decoded,
+// unclosed elements are closed the way browsers do for the ones found in forms and lists.
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    /// Lowercase tag name, `#document` for the root
+    pub name: String,
+    /// Lowercase names, decoded values, in document order
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+/// Elements that never have children
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+/// Elements whose content is not HTML
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements implicitly closed by the start of another one || (opening, closed)
+const IMPLICIT_CLOSES: &[(&str, &[&str])] = &[
+    ("option", &["option"]),
+    ("optgroup", &["option", "optgroup"]),
+    ("li", &["li"]),
+    ("p", &["p"]),
+    ("tr", &["td", "th", "tr"]),
+    ("td", &["td", "th"]),
+    ("th", &["td", "th"]),
+];
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Child elements, text nodes are skipped
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Every element below this one with the given tag name, in document order
+    pub fn find_all(&self, name: &str) -> Vec<&Element> {
+        let mut found = vec![];
+        for element in self.elements() {
+            if element.name == name {
+                found.push(element);
+            }
+            found.extend(element.find_all(name));
+        }
+        found
+    }
+
+    /// Text content with whitespace collapsed
+    pub fn text(&self) -> String {
+        fn collect(element: &Element, text: &mut String) {
+            for node in &element.children {
+                match node {
+                    Node::Text(content) => text.push_str(content),
+                    Node::Element(child) => {
+                        text.push(' ');
+                        collect(child, text);
+                        text.push(' ');
+                    }
+                }
+            }
+        }
+
+        let mut text = String::new();
+        collect(self, &mut text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Parses a whole page, the returned `#document` element holds the top level nodes
+pub fn parse(html: &str) -> Element {
+    let mut stack: Vec<Element> = vec![Element {
+        name: "#document".into(),
+        ..Element::default()
+    }];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        // Text up to the next tag
+        let Some(start) = rest.find('<') else {
+            push_text(&mut stack, rest);
+            break;
+        };
+        push_text(&mut stack, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(tag) = rest.strip_prefix("</") {
+            let end = tag.find('>').unwrap_or(tag.len());
+            close(&mut stack, &tag[..end].trim().to_lowercase());
+            rest = tag.get(end + 1..).unwrap_or("");
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (element, self_closing, remaining) = parse_start_tag(&rest[1..]);
+            rest = remaining;
+
+            open(&mut stack, element, self_closing);
+
+            // Skip the content of raw text elements up to their end tag
+            let name = &stack.last().unwrap().name;
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let end_tag = format!("</{}", name);
+                let end = rest.to_ascii_lowercase().find(&end_tag).unwrap_or(rest.len());
+                push_text(&mut stack, &rest[..end]);
+                rest = &rest[end..];
+            }
+        } else {
+            // A lone '<' is just text
+            push_text(&mut stack, "<");
+            rest = &rest[1..];
+        }
+    }
+
+    while stack.len() > 1 {
+        pop(&mut stack);
+    }
+    stack.pop().unwrap()
+}
+
+/// Parses `name attr="value" ...>`, returns the element, whether it was self closing and what follows the tag
+fn parse_start_tag(input: &str) -> (Element, bool, &str) {
+    let name_end = input
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(input.len());
+    let mut element = Element {
+        name: input[..name_end].to_lowercase(),
+        ..Element::default()
+    };
+    let mut rest = &input[name_end..];
+    let mut self_closing = false;
+
+    loop {
+        rest = rest.trim_start();
+
+        if let Some(remaining) = rest.strip_prefix('>') {
+            return (element, self_closing, remaining);
+        }
+        if let Some(remaining) = rest.strip_prefix('/') {
+            self_closing = true;
+            rest = remaining;
+            continue;
+        }
+        if rest.is_empty() {
+            return (element, self_closing, rest);
+        }
+
+        let key_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+            .unwrap_or(rest.len())
+            .max(1);
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+        self_closing = false;
+
+        let value = match rest.strip_prefix('=') {
+            Some(remaining) => {
+                let remaining = remaining.trim_start();
+                match remaining.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let inner = &remaining[1..];
+                        let end = inner.find(quote).unwrap_or(inner.len());
+                        rest = inner.get(end + 1..).unwrap_or("");
+                        &inner[..end]
+                    }
+                    _ => {
+                        let end = remaining
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(remaining.len());
+                        rest = &remaining[end..];
+                        &remaining[..end]
+                    }
+                }
+            }
+            None => "",
+        };
+
+        // The first occurrence of an attribute wins, as in browsers
+        if element.attr(&key).is_none() {
+            element.attributes.push((key, decode_entities(value)));
+        }
+    }
+}
+
+fn open(stack: &mut Vec<Element>, element: Element, self_closing: bool) {
+    if let Some((_, closed)) = IMPLICIT_CLOSES.iter().find(|(opening, _)| *opening == element.name) {
+        while stack.len() > 1 && closed.contains(&stack.last().unwrap().name.as_str()) {
+            pop(stack);
+        }
+    }
+
+    if self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+        stack.last_mut().unwrap().children.push(Node::Element(element));
+    } else {
+        stack.push(element);
+    }
+}
+
+/// Closes the innermost open element with the given name, end tags without a match are ignored
+fn close(stack: &mut Vec<Element>, name: &str) {
+    if let Some(position) = stack.iter().skip(1).rposition(|element| element.name == name) {
+        while stack.len() > position + 1 {
+            pop(stack);
+        }
+    }
+}
+
+fn pop(stack: &mut Vec<Element>) {
+    let element = stack.pop().unwrap();
+    stack.last_mut().unwrap().children.push(Node::Element(element));
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    if !text.is_empty() {
+        let raw = RAW_TEXT_ELEMENTS.contains(&stack.last().unwrap().name.as_str());
+        let text = if raw { text.to_string() } else { decode_entities(text) };
+        stack.last_mut().unwrap().children.push(Node::Text(text));
+    }
+}
+
+/// Decodes numeric entities and the common named ones (every Latin-1 one included), unknown ones are kept as they are
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 32)
+            .map(|end| &rest[1..end + 1]);
+
+        let character = entity.and_then(|entity| match entity.strip_prefix('#') {
+            Some(code) => match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => code.parse().ok(),
+            }
+            .and_then(char::from_u32),
+            None => named_entity(entity),
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Names of the Latin-1 entities, in the order of their characters from U+00A0 to U+00FF
+const LATIN_1_ENTITIES: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf", "laquo",
+    "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro", "para", "middot",
+    "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest", "Agrave", "Aacute", "Acirc", "Atilde",
+    "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute", "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml",
+    "ETH", "Ntilde", "Ograve", "Oacute", "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc",
+    "Uuml", "Yacute", "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde", "ograve", "oacute",
+    "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc", "uuml", "yacute", "thorn", "yuml",
+];
+/// Named entities outside of Latin-1: markup, punctuation and the letters of Windows-1252
+const OTHER_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'), ("lt", '<'), ("gt", '>'), ("quot", '"'), ("apos", '\''),
+    ("ensp", '\u{2002}'), ("emsp", '\u{2003}'), ("thinsp", '\u{2009}'), ("zwnj", '\u{200c}'), ("zwj", '\u{200d}'),
+    ("ndash", '–'), ("mdash", '—'), ("lsquo", '‘'), ("rsquo", '’'), ("sbquo", '‚'),
+    ("ldquo", '“'), ("rdquo", '”'), ("bdquo", '„'), ("lsaquo", '‹'), ("rsaquo", '›'),
+    ("dagger", '†'), ("Dagger", '‡'), ("bull", '•'), ("hellip", '…'), ("permil", '‰'),
+    ("prime", '′'), ("Prime", '″'), ("euro", '€'), ("trade", '™'), ("circ", 'ˆ'), ("tilde", '˜'),
+    ("OElig", 'Œ'), ("oelig", 'œ'), ("Scaron", 'Š'), ("scaron", 'š'), ("Yuml", 'Ÿ'), ("fnof", 'ƒ'),
+];
+
+fn named_entity(name: &str) -> Option<char> {
+    if let Some(offset) = LATIN_1_ENTITIES.iter().position(|entity| *entity == name) {
+        return char::from_u32(0xa0 + offset as u32);
+    }
+
+    OTHER_ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, character)| *character)
+}
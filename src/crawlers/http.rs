@@ -0,0 +1,270 @@
+// External libraries
+use log::warn;
+use reqwest::{Client, StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Internal modules
+use super::rate_limit::HostLimiter;
+use super::recorder::{Exchange, Recording};
+use crate::models::error::{Error, ErrorFault};
+
+/// Settings of the HTTP client used by a crawler.
+///
+/// Every setting is read from `HTTP_<SETTING>_<UNIVERSITY>`, then `HTTP_<SETTING>`, then the crawler default:
+/// - `CONNECT_TIMEOUT`, `READ_TIMEOUT`, `TIMEOUT`: seconds
+/// - `RETRIES`: attempts after the first one on transient errors
+/// - `BACKOFF`: milliseconds before the first retry, doubled at every attempt up to `TIMEOUT`
+/// - `USER_AGENT`
+/// - `RATE_LIMIT`: requests per second towards each host, `0` disables it
+/// - `BURST`: requests that can be sent at once before the rate limit kicks in
+/// - `MAX_CONCURRENCY`: requests in flight towards each host
+/// - `QUEUE_TIMEOUT`: seconds a request waits for its turn before failing with a 503
+///
+/// Exchanges can also be recorded to or replayed from disk, see `Recording`.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    /// Upper bound for a whole exchange, body included
+    pub timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+    pub user_agent: String,
+    pub rate_limit: f64,
+    pub burst: u32,
+    pub max_concurrency: usize,
+    pub queue_timeout: Duration,
+    pub recording: Recording,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(15),
+            timeout: Duration::from_secs(30),
+            retries: 2,
+            backoff: Duration::from_millis(250),
+            user_agent: format!(
+                "TimeTable/{} (+https://github.com/jacopofilonzi/TimeTable)",
+                env!("CARGO_PKG_VERSION")
+            ),
+            rate_limit: 2.0,
+            burst: 4,
+            max_concurrency: 4,
+            queue_timeout: Duration::from_secs(10),
+            recording: Recording::Off,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Applies the environment overrides of the university on top of the given defaults
+    pub fn from_env(university: &str, defaults: HttpConfig) -> Self {
+        let read = |name: &str| -> Option<String> {
+            std::env::var(format!("HTTP_{}_{}", name, university.to_uppercase()))
+                .or_else(|_| std::env::var(format!("HTTP_{}", name)))
+                .ok()
+        };
+        let seconds = |name: &str, default: Duration| {
+            read(name)
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default)
+        };
+
+        Self {
+            connect_timeout: seconds("CONNECT_TIMEOUT", defaults.connect_timeout),
+            read_timeout: seconds("READ_TIMEOUT", defaults.read_timeout),
+            timeout: seconds("TIMEOUT", defaults.timeout),
+            retries: read("RETRIES")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.retries),
+            backoff: read("BACKOFF")
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.backoff),
+            user_agent: read("USER_AGENT").unwrap_or(defaults.user_agent),
+            rate_limit: read("RATE_LIMIT")
+                .and_then(|s| s.parse().ok())
+                .filter(|rate: &f64| rate.is_finite() && *rate >= 0.0)
+                .unwrap_or(defaults.rate_limit),
+            burst: read("BURST")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.burst),
+            max_concurrency: read("MAX_CONCURRENCY")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_concurrency),
+            queue_timeout: seconds("QUEUE_TIMEOUT", defaults.queue_timeout),
+            recording: match Recording::from_env(university) {
+                Recording::Off => defaults.recording,
+                recording => recording,
+            },
+        }
+    }
+}
+
+/// Pooled HTTP client of a crawler, with timeouts, retries on transient errors and per host rate limiting
+pub struct HttpClient {
+    client: Client,
+    config: HttpConfig,
+    limiters: Mutex<HashMap<String, Arc<HostLimiter>>>,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Self {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
+            .timeout(config.timeout)
+            .user_agent(&config.user_agent)
+            .build()
+            .expect("Failed to build the HTTP client");
+
+        Self {
+            client,
+            config,
+            limiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends a GET request and returns the body of the successful response.
+    /// Connection errors, timeouts, 429 and 5xx responses are retried with exponential backoff.
+    pub async fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<String, Error> {
+        let mut attempt = 0;
+
+        loop {
+            let result = self.get_once(url, query).await;
+
+            match result {
+                Err((err, true)) if attempt < self.config.retries => {
+                    // Waiting longer than a whole exchange can take is pointless, and the doubling overflows after a while
+                    let delay = 2u32
+                        .checked_pow(attempt)
+                        .and_then(|factor| self.config.backoff.checked_mul(factor))
+                        .map_or(self.config.timeout, |delay| delay.min(self.config.timeout));
+                    warn!(
+                        "Request to {} failed ({}), retrying in {:?}",
+                        url,
+                        err.message.as_deref().unwrap_or_default(),
+                        delay
+                    );
+
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(|(err, _)| err),
+            }
+        }
+    }
+
+    /// Single attempt, the error is paired with whether it is worth retrying
+    async fn get_once(&self, url: &str, query: &[(&str, &str)]) -> Result<String, (Error, bool)> {
+        // Recorded exchanges stand in for the university when replaying
+        let (exchange, replayed) = match self.config.recording.replay(url, query) {
+            Some(replayed) => (replayed.map_err(|err| (err, false))?, true),
+            None => {
+                let exchange = self.fetch(url, query).await?;
+                self.config.recording.record(&exchange);
+                (exchange, false)
+            }
+        };
+
+        let status = StatusCode::from_u16(exchange.status).unwrap_or(StatusCode::BAD_GATEWAY);
+        if status != StatusCode::OK {
+            let transient = !replayed
+                && (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error());
+            return Err((
+                Self::error(format!("The server responded with status code: {}", status)),
+                transient,
+            ));
+        }
+
+        Ok(exchange.body)
+    }
+
+    /// Sends the request once its host allows it
+    async fn fetch(&self, url: &str, query: &[(&str, &str)]) -> Result<Exchange, (Error, bool)> {
+        let limiter = self.limiter(url);
+        let Some(_permit) = limiter.acquire(self.config.queue_timeout).await else {
+            return Err((
+                Error {
+                    error: "The university is receiving too many requests".into(),
+                    http_code: Some(503),
+                    message: Some(format!(
+                        "Request to {} still queued after {:?}",
+                        url, self.config.queue_timeout
+                    )),
+                    fault: ErrorFault::External,
+                    retry_after: None,
+                },
+                false,
+            ));
+        };
+
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|err| {
+                let transient = err.is_connect() || err.is_timeout() || err.is_request();
+                (
+                    Self::error(format!("Connection error while attempting to fetch: {}", err)),
+                    transient,
+                )
+            })?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(|err| {
+            (
+                Self::error(format!("Error while reading the response body: {}", err)),
+                err.is_timeout(),
+            )
+        })?;
+
+        Ok(Exchange {
+            url: url.to_string(),
+            query: query
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            status: status.as_u16(),
+            body,
+        })
+    }
+
+    /// Limiter of the host of the url, created on first use
+    fn limiter(&self, url: &str) -> Arc<HostLimiter> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+
+        self.limiters
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| {
+                Arc::new(HostLimiter::new(
+                    self.config.rate_limit,
+                    self.config.burst,
+                    self.config.max_concurrency,
+                ))
+            })
+            .clone()
+    }
+
+    fn error(message: String) -> Error {
+        Error {
+            error: "Error while contacting the university".into(),
+            http_code: None,
+            message: Some(message),
+            fault: ErrorFault::External,
+            retry_after: None,
+        }
+    }
+}
@@ -1,135 +1,226 @@
 // External libraries
 use actix_web::web::Data;
 use async_trait::async_trait;
-use log::warn;
-use md5;
-use redis::{Client, Commands, RedisResult};
+use futures_util::future::join_all;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 // Internal modules
-use crate::models::{course::Course, error::Error, lesson::Lesson};
+use crate::cache::{
+    main::{Cache, CacheStatus, Cached},
+    store::Caches,
+};
+use crate::models::{
+    course::{Course, CourseRef},
+    date_range::DateRange,
+    error::Error,
+    lesson::Lesson,
+    query::{CourseQuery, LessonQuery},
+    university::{Capability, University},
+};
 
 // This trait is the common interface for all crawlers
 #[async_trait]
-pub trait UniversityCrawler: Send + Sync {
+pub trait UniversityCrawler: Send + Sync + 'static {
     // To be implemented by each crawler
 
-    /// Fetches lessons based on the provided query parameters.
-    async fn get_lessons(&self, query: HashMap<String, String>) -> Result<Vec<Lesson>, Error>;
+    /// Describes the university, its capabilities and the accepted query parameters.
+    fn metadata(&self) -> University;
+    /// Fetches lessons based on the provided query parameters, limited to the given range of days.
+    /// The shared parameters have already been validated, crawler specific ones are in `query.extra` and declared in `metadata`.
+    async fn get_lessons(&self, query: LessonQuery, range: DateRange)
+    -> Result<Vec<Lesson>, Error>;
     /// Fetches courses based on the provided query parameters.
-    async fn get_courses(&self, query: HashMap<String, String>) -> Result<Vec<Course>, Error>;
+    async fn get_courses(&self, query: CourseQuery) -> Result<Vec<Course>, Error>;
 
     // ================ Caching methods =================
     // This methods are common for all crawlers to implement caching by hashing query paramethers of the request
-    // and shouldn't be overridden by the crawlers
+    // and shouldn't be overridden by the crawlers.
+    // They take the crawler behind an `Arc` so stale entries can be refreshed in the background.
 
     /// Returns cached lessons
     async fn get_cached_lessons(
-        &self,
+        self: Arc<Self>,
         university: String,
-        query: HashMap<String, String>,
-        redis_client: Data<Client>,
-    ) -> Result<Vec<Lesson>, Error> {
-        // Get a connection to Redis
-        let mut redis_conn = match redis_client.get_connection() {
-            Ok(conn) => conn, //OK
-            Err(err) => {
-                // Connection failed, skip cache and crawl
-                warn!("Failed to connect to Redis: {}", err);
-                return self.get_lessons(query).await;
+        query: LessonQuery,
+        caches: Data<Caches>,
+    ) -> Result<Cached<Vec<Lesson>>, Error> {
+        // Validate the parameters and the requested time span before doing anything else
+        query.validate()?;
+        let metadata = self.metadata();
+        metadata.check_course_year(query.course_year)?;
+        metadata.check_parameters(Capability::Lessons, &query.extra)?;
+        let range = query.date_range(metadata.timezone)?;
+
+        let cache_key = lessons_cache_key(&university, &query, &range);
+
+        let index = caches.clone();
+        return caches
+            .lessons
+            .get_or_fetch(&cache_key, move || {
+                crawl_and_index_lessons(self, university, query, range, index)
+            })
+            .await;
+    }
+
+    // -----------------------------------------------------------------------------------------------------------------------
+
+    /// Returns the cached lessons of several courses merged into a single timetable, sorted by start.
+    /// Every course goes through `get_cached_lessons`, a lesson shared by more courses appears once, tagged with all of them.
+    /// The status is the least fresh among the courses.
+    async fn get_cached_timetable(
+        self: Arc<Self>,
+        university: String,
+        queries: Vec<LessonQuery>,
+        caches: Data<Caches>,
+    ) -> Result<Cached<Vec<Lesson>>, Error> {
+        // The courses are crawled side by side, each one through its own cache entry
+        let results = join_all(queries.into_iter().map(|query| {
+            self.clone()
+                .get_cached_lessons(university.clone(), query, caches.clone())
+        }))
+        .await;
+
+        let mut merged: Vec<Lesson> = vec![];
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut status = CacheStatus::Hit;
+
+        for lessons in results {
+            let lessons = lessons?;
+            status = status.max(lessons.status);
+
+            for lesson in lessons.value {
+                match positions.get(&lesson.id) {
+                    Some(&position) => {
+                        let merged = &mut merged[position];
+                        for course in lesson.courses {
+                            if !merged.courses.contains(&course) {
+                                merged.courses.push(course);
+                            }
+                        }
+                    }
+                    None => {
+                        positions.insert(lesson.id.clone(), merged.len());
+                        merged.push(lesson);
+                    }
+                }
             }
-        };
+        }
 
-        // Sort the query parameters to ensure consistent hashing regardless of order
-        let mut sorted_query: Vec<(&String, &String)> = query.iter().collect();
-        sorted_query.sort_by_key(|&(k, _)| k);
+        merged.sort_by(|a, b| a.starts_at.cmp(&b.starts_at).then_with(|| a.id.cmp(&b.id)));
 
-        // Create a hash of the query parameters to use as a key
-        let query_hash = format!(
-            "{:x}",
-            md5::compute(serde_json::to_string(&sorted_query).unwrap())
-        );
+        return Ok(Cached {
+            value: merged,
+            status,
+        });
+    }
 
-        // Fetch cache
-        let cache_result: RedisResult<String> =
-            redis_conn.get(format!("lessons:{}:{}", university, query_hash));
+    // -----------------------------------------------------------------------------------------------------------------------
 
-        match cache_result {
-            Ok(lessons) => {
-                // Cache hit
-                return Ok(serde_json::from_str::<Vec<Lesson>>(&lessons).unwrap());
-            }
-            Err(_) => {
-                // Cache miss, fetch from crawler
-                // Crawl
-                let _lessons = self.get_lessons(query).await?;
-
-                // Cache the courses for 3 days || key -> `lessons:<university>:<sorted_query_hash>`
-                let _: RedisResult<()> = redis_conn.set_ex(
-                    format!("lessons:{}:{}", university, query_hash),
-                    serde_json::to_string(&_lessons).unwrap(),
-                    60 * 60 * 24 * 3,
-                );
-
-                // Return the courses fetched from the crawler
-                return Ok(_lessons);
-            }
-        }
+    /// Crawls the lessons again if they are missing from the cache or about to expire within `margin` seconds.
+    /// Returns whether a crawl happened.
+    async fn prewarm_lessons(
+        self: Arc<Self>,
+        university: String,
+        query: LessonQuery,
+        caches: Data<Caches>,
+        margin: u64,
+    ) -> Result<bool, Error> {
+        query.validate()?;
+        let metadata = self.metadata();
+        metadata.check_course_year(query.course_year)?;
+        metadata.check_parameters(Capability::Lessons, &query.extra)?;
+        let range = query.date_range(metadata.timezone)?;
+
+        let cache_key = lessons_cache_key(&university, &query, &range);
+
+        let index = caches.clone();
+        return caches
+            .lessons
+            .prewarm(&cache_key, margin, move || {
+                crawl_and_index_lessons(self, university, query, range, index)
+            })
+            .await;
     }
 
     // -----------------------------------------------------------------------------------------------------------------------
 
-    /// Returns cached courses
-    async fn get_cached_courses(
-        &self,
+    /// Returns a single lesson previously cached by `get_cached_lessons`
+    async fn get_cached_lesson(
+        self: Arc<Self>,
         university: String,
-        query: HashMap<String, String>,
-        redis_client: Data<Client>,
-    ) -> Result<Vec<Course>, Error> {
-        // Get a connection to Redis
-        let mut redis_conn = match redis_client.get_connection() {
-            Ok(conn) => conn, //OK
-            Err(err) => {
-                // Connection failed, skip cache and crawl
-                warn!("Failed to connect to Redis: {}", err);
-                return self.get_courses(query).await;
-            }
-        };
+        id: String,
+        caches: Data<Caches>,
+    ) -> Result<Option<Lesson>, Error> {
+        return Ok(caches.lesson.get(&format!("{}:{}", university, id)).await);
+    }
 
-        // Sort the query parameters to ensure consistent hashing regardless of order
-        let mut sorted_query: Vec<(&String, &String)> = query.iter().collect();
-        sorted_query.sort_by_key(|&(k, _)| k);
+    // -----------------------------------------------------------------------------------------------------------------------
 
-        // Create a hash of the query parameters to use as a key
-        let query_hash = format!(
-            "{:x}",
-            md5::compute(serde_json::to_string(&sorted_query).unwrap())
+    /// Returns cached courses
+    async fn get_cached_courses(
+        self: Arc<Self>,
+        university: String,
+        query: CourseQuery,
+        caches: Data<Caches>,
+    ) -> Result<Cached<Vec<Course>>, Error> {
+        // Hash the query parameters (extra parameters are already sorted), once they are known to be declared
+        self.metadata().check_parameters(Capability::Courses, &query.extra)?;
+        let cache_key = format!(
+            "{}:{}",
+            university,
+            Cache::<Vec<Course>>::hash_key(&query.extra)
         );
 
-        // Fetch cache
-        let cache_result: RedisResult<String> =
-            redis_conn.get(format!("courses:{}:{}", university, query_hash));
+        return caches
+            .courses
+            .get_or_fetch(&cache_key, move || async move {
+                self.get_courses(query).await
+            })
+            .await;
+    }
+}
+
+/// Hashes the query parameters (extra parameters are already sorted and declared by the crawler),
+/// the time span parameters are left out as the resolved range is part of the key
+fn lessons_cache_key(university: &str, query: &LessonQuery, range: &DateRange) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        university,
+        range.from,
+        range.to,
+        Cache::<Vec<Lesson>>::hash_key(&(&query.course_id, query.course_year, &query.extra))
+    )
+}
 
-        match cache_result {
-            Ok(courses) => {
-                // Cache hit
-                return Ok(serde_json::from_str::<Vec<Course>>(&courses).unwrap());
-            }
-            Err(_) => {
-                // Cache miss, fetch from crawler
-                // Crawl
-                let _courses = self.get_courses(query).await?;
-
-                // Cache the courses for 3 months || key -> `courses:<university>:<sorted_query_hash>`
-                let _: RedisResult<()> = redis_conn.set_ex(
-                    format!("courses:{}:{}", university, query_hash),
-                    serde_json::to_string(&_courses).unwrap(),
-                    60 * 60 * 24 * 90,
-                );
-
-                // Return the courses fetched from the crawler
-                return Ok(_courses);
-            }
+/// Crawls the lessons, tags them with the crawled course and indexes every one of them by id
+async fn crawl_and_index_lessons<C: UniversityCrawler + ?Sized>(
+    crawler: Arc<C>,
+    university: String,
+    query: LessonQuery,
+    range: DateRange,
+    caches: Data<Caches>,
+) -> Result<Vec<Lesson>, Error> {
+    let course = CourseRef {
+        id: query.course_id.clone(),
+        year: query.course_year,
+    };
+
+    let mut lessons = crawler.get_lessons(query, range).await?;
+    for lesson in &mut lessons {
+        if !lesson.courses.contains(&course) {
+            lesson.courses.push(course.clone());
         }
     }
+
+    let ids: Vec<String> = lessons
+        .iter()
+        .map(|lesson| format!("{}:{}", university, lesson.id))
+        .collect();
+    caches
+        .lesson
+        .set_many(ids.iter().map(String::as_str).zip(lessons.iter()))
+        .await;
+
+    Ok(lessons)
 }
@@ -1,3 +1,9 @@
+pub mod breaker;
+pub mod html;
+pub mod http;
 pub mod main;
+pub mod rate_limit;
+pub mod recorder;
+pub mod rooms;
 pub mod store;
 pub mod unicam;
\ No newline at end of file
@@ -0,0 +1,80 @@
+// External libraries
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Politeness towards a single upstream host: a token bucket caps the request rate
+/// and a semaphore caps the requests in flight
+pub struct HostLimiter {
+    /// Tokens added every second, `0` disables the rate limit
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    concurrency: Semaphore,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl HostLimiter {
+    pub fn new(rate: f64, burst: u32, max_concurrency: usize) -> Self {
+        let burst = burst.max(1) as f64;
+
+        Self {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+            concurrency: Semaphore::new(max_concurrency.max(1)),
+        }
+    }
+
+    /// Waits for a free slot and a token, `None` if neither came within the timeout.
+    /// The slot is released when the permit is dropped.
+    pub async fn acquire(&self, timeout: Duration) -> Option<SemaphorePermit<'_>> {
+        let deadline = Instant::now() + timeout;
+
+        let permit = tokio::time::timeout_at(deadline, self.concurrency.acquire())
+            .await
+            .ok()?
+            .ok()?;
+
+        loop {
+            let wait = self.take_token();
+            if wait.is_zero() {
+                return Some(permit);
+            }
+
+            if wait > deadline.saturating_duration_since(Instant::now()) {
+                return None;
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token if available, otherwise returns how long until the next one
+    fn take_token(&self) -> Duration {
+        if self.rate <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Duration::ZERO;
+        }
+
+        Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.rate).unwrap_or(Duration::MAX)
+    }
+}
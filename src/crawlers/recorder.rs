@@ -0,0 +1,95 @@
+// External libraries
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Internal modules
+use crate::models::error::{Error, ErrorFault};
+
+/// Upstream HTTP exchange as stored on disk, one JSON file each
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Requested url, without the query
+    pub url: String,
+    /// Query parameters in the order they were sent
+    pub query: Vec<(String, String)>,
+    pub status: u16,
+    pub body: String,
+}
+
+/// Whether the crawler HTTP exchanges are written to or read from disk.
+///
+/// - `CRAWLER_RECORD_DIR`: every exchange is written to `<dir>/<university>/<hash>.json`
+/// - `CRAWLER_REPLAY_DIR`: exchanges are read from `<dir>/<university>/*.json` instead of going to the network
+#[derive(Debug, Clone, Default)]
+pub enum Recording {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl Recording {
+    pub fn from_env(university: &str) -> Self {
+        if let Ok(dir) = std::env::var("CRAWLER_REPLAY_DIR") {
+            return Recording::Replay(PathBuf::from(dir).join(university));
+        }
+        if let Ok(dir) = std::env::var("CRAWLER_RECORD_DIR") {
+            return Recording::Record(PathBuf::from(dir).join(university));
+        }
+
+        return Recording::Off;
+    }
+
+    /// Writes the exchange if recording, failures are only logged.
+    /// The same request always lands in the same file, so only its latest exchange is kept.
+    pub fn record(&self, exchange: &Exchange) {
+        let Recording::Record(dir) = self else {
+            return;
+        };
+
+        let path = dir.join(format!(
+            "{:x}.json",
+            md5::compute(serde_json::to_string(&(&exchange.url, &exchange.query)).unwrap())
+        ));
+
+        let result = std::fs::create_dir_all(dir).and_then(|_| {
+            std::fs::write(&path, serde_json::to_string_pretty(exchange).unwrap())
+        });
+
+        match result {
+            Ok(_) => info!("Recorded {} to {}", exchange.url, path.display()),
+            Err(err) => warn!("Failed to record {} to {}: {}", exchange.url, path.display(), err),
+        }
+    }
+
+    /// Looks up the recorded exchange of the request, `None` when not replaying.
+    /// Every file of the directory is considered, so recordings can be renamed freely.
+    pub fn replay(&self, url: &str, query: &[(&str, &str)]) -> Option<Result<Exchange, Error>> {
+        let Recording::Replay(dir) = self else {
+            return None;
+        };
+
+        let recorded = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .filter_map(|content| serde_json::from_str::<Exchange>(&content).ok())
+            .find(|exchange| {
+                exchange.url == url
+                    && exchange.query.len() == query.len()
+                    && exchange.query.iter().zip(query).all(|((k1, v1), (k2, v2))| k1 == k2 && v1 == v2)
+            });
+
+        return Some(recorded.ok_or(Error {
+            error: "Error while contacting the university".into(),
+            http_code: None,
+            message: Some(format!("No recorded exchange for {} {:?} in {}", url, query, dir.display())),
+            fault: ErrorFault::External,
+            retry_after: None,
+        }));
+    }
+}
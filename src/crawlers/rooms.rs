@@ -0,0 +1,87 @@
+// External libraries
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::Path;
+
+// Internal modules
+use crate::models::location::Location;
+
+/// Static details about the rooms and buildings of a university, read from `<ROOM_CATALOG_DIR>/<university>.json`.
+/// The file is a list of entries, each one describing a building or, if `room` is set, a single room of it:
+/// `[{ "building": "Polo Lodovici", "campus": "Camerino", "address": "...", "latitude": 43.1, "longitude": 13.1 }]`
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct RoomCatalog {
+    entries: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogEntry {
+    building: String,
+    room: Option<String>,
+    campus: Option<String>,
+    address: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+impl RoomCatalog {
+    /// Loads the catalog of the university, empty when `ROOM_CATALOG_DIR` is not set
+    pub fn from_env(university: &str) -> Self {
+        match std::env::var("ROOM_CATALOG_DIR") {
+            Ok(dir) => Self::load(&Path::new(&dir).join(format!("{}.json", university))),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Loads a catalog file, a missing or invalid file leaves the catalog empty
+    pub fn load(path: &Path) -> Self {
+        let catalog = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| serde_json::from_str::<RoomCatalog>(&content).map_err(|err| err.to_string()));
+
+        match catalog {
+            Ok(catalog) => {
+                info!("Loaded {} room catalog entries from {}", catalog.entries.len(), path.display());
+                catalog
+            }
+            Err(err) => {
+                warn!("Room catalog {} not loaded: {}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    /// Fills in what the crawler couldn't find, the entry of the room wins over the one of its building
+    pub fn complete(&self, location: &mut Location) {
+        let Some(building) = location.building.as_deref().map(normalize) else {
+            return;
+        };
+        let room = location.room.as_deref().map(normalize);
+
+        let entry = self
+            .entries
+            .iter()
+            .filter(|entry| normalize(&entry.building) == building)
+            .find(|entry| entry.room.is_some() && entry.room.as_deref().map(normalize) == room)
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .find(|entry| entry.room.is_none() && normalize(&entry.building) == building)
+            });
+
+        if let Some(entry) = entry {
+            location.campus = location.campus.take().or(entry.campus.clone());
+            location.address = location.address.take().or(entry.address.clone());
+            if location.coordinates().is_none() {
+                location.latitude = entry.latitude;
+                location.longitude = entry.longitude;
+            }
+        }
+    }
+}
+
+/// Case and whitespace insensitive comparison key
+fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
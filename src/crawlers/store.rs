@@ -1,12 +1,112 @@
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::breaker::{ BreakerConfig, CircuitBreaker, GuardedCrawler };
 use super::main::{ UniversityCrawler };
 use super::unicam;
 
 
 
-pub fn get_university_crawler(name: &str) -> Option<Box<dyn UniversityCrawler>> {
-    match name {
-        "unicam" => Some(Box::new(unicam::UnicamCrawler)),
+/// Every crawler shipped with the server
+fn all_crawlers() -> Vec<Arc<dyn UniversityCrawler>> {
+    vec![
+        Arc::new(unicam::UnicamCrawler::new()),
         // Add other crawlers here
-        _ => None,
+    ]
+}
+
+/// Crawlers enabled for this deployment, built once at startup and shared across handlers.
+/// Every crawler is wrapped in its own circuit breaker.
+pub struct CrawlerRegistry {
+    crawlers: BTreeMap<&'static str, Arc<dyn UniversityCrawler>>,
+    breakers: BTreeMap<&'static str, Arc<CircuitBreaker>>,
+}
+
+impl CrawlerRegistry {
+    /// Builds the registry, filtered by the comma separated `CRAWLERS_ENABLED` (default: all)
+    /// and `CRAWLERS_DISABLED` (default: none) environment variables
+    pub fn from_env() -> Self {
+        let registry = Self::select(
+            all_crawlers(),
+            std::env::var("CRAWLERS_ENABLED").ok().as_deref(),
+            std::env::var("CRAWLERS_DISABLED").ok().as_deref(),
+        );
+        info!("Enabled crawlers: {:?}", registry.crawlers.keys().collect::<Vec<_>>());
+
+        registry
+    }
+
+    /// Builds the registry out of the crawlers listed in `enabled` (all of them when missing) and not in `disabled`.
+    /// Lists are comma separated and case insensitive, unknown ids are ignored with a warning.
+    pub fn select(crawlers: Vec<Arc<dyn UniversityCrawler>>, enabled: Option<&str>, disabled: Option<&str>) -> Self {
+        let ids: Vec<&str> = crawlers.iter().map(|crawler| crawler.metadata().id).collect();
+        let read_list = |name: &str, list: Option<&str>| -> Option<Vec<String>> {
+            list.map(|list| {
+                list.split(',')
+                    .map(|id| id.trim().to_lowercase())
+                    .filter(|id| !id.is_empty())
+                    .inspect(|id| {
+                        if !ids.contains(&id.as_str()) {
+                            warn!("{} lists the unknown crawler '{}'", name, id);
+                        }
+                    })
+                    .collect()
+            })
+        };
+
+        // A blank list of enabled crawlers counts as missing, rather than disabling every one of them
+        let enabled = read_list("CRAWLERS_ENABLED", enabled).filter(|enabled| !enabled.is_empty());
+        let disabled = read_list("CRAWLERS_DISABLED", disabled).unwrap_or_default();
+
+        let crawlers = crawlers
+            .into_iter()
+            .filter(|crawler| {
+                let id = crawler.metadata().id;
+                enabled.as_ref().is_none_or(|enabled| enabled.iter().any(|e| e == id))
+                    && !disabled.iter().any(|d| d == id)
+            })
+            .collect();
+
+        Self::new(crawlers)
+    }
+
+    /// Builds the registry out of the given crawlers, wrapping each one in its circuit breaker
+    pub fn new(crawlers: Vec<Arc<dyn UniversityCrawler>>) -> Self {
+        let crawlers = crawlers
+            .into_iter()
+            .map(|crawler| (crawler.metadata().id, crawler))
+            .collect::<BTreeMap<_, _>>();
+
+        let breakers = crawlers
+            .keys()
+            .map(|id| (*id, Arc::new(CircuitBreaker::new(id, BreakerConfig::from_env(id)))))
+            .collect::<BTreeMap<_, _>>();
+
+        let crawlers = crawlers
+            .into_iter()
+            .map(|(id, crawler)| {
+                let guarded: Arc<dyn UniversityCrawler> =
+                    Arc::new(GuardedCrawler::new(crawler, breakers[id].clone()));
+                (id, guarded)
+            })
+            .collect();
+
+        Self { crawlers, breakers }
+    }
+
+    /// Returns the crawler of the university, if enabled
+    pub fn get(&self, university: &str) -> Option<Arc<dyn UniversityCrawler>> {
+        self.crawlers.get(university).cloned()
+    }
+
+    /// Enabled crawlers, sorted by id
+    pub fn all(&self) -> impl Iterator<Item = &Arc<dyn UniversityCrawler>> {
+        self.crawlers.values()
+    }
+
+    /// Circuit breakers of the enabled crawlers, sorted by id
+    pub fn breakers(&self) -> impl Iterator<Item = (&&'static str, &Arc<CircuitBreaker>)> {
+        self.breakers.iter()
     }
-}
\ No newline at end of file
+}
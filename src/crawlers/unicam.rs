@@ -1,100 +1,356 @@
 // External libraries
-use chrono::{Datelike, Utc, Duration};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use async_trait::async_trait;
-use reqwest::{Client};
-use regex::Regex;
+use std::ops::RangeInclusive;
 
 // Internal modules
-use crate::models::{ lesson::Lesson, course::Course, error::{ Error, ErrorFault }};
+use crate::models::{ lesson::Lesson, course::Course, date_range::DateRange, error::{ Error, ErrorFault }, lesson_kind::{ LessonKind, LessonStatus }, location::Location, parse_warning::{ ParseWarning, ParseWarningKind }, query::{ CourseQuery, LessonQuery }, teacher::Teacher, university::{ Capability, University }};
+use super::html::{ self, Element, Node };
+use super::http::{ HttpClient, HttpConfig };
 use super::main::{ UniversityCrawler };
+use super::rooms::RoomCatalog;
+
+
+
+/// Timezone the upstream timetable is expressed in
+const TIMEZONE: Tz = chrono_tz::Europe::Rome;
+/// Years the upstream timetable lists the courses under
+const COURSE_YEARS: RangeInclusive<u8> = 0..=5;
+/// Host serving the course list
+const COURSES_BASE_URL: &str = "https://orarilezioni.unicam.it";
+/// Host serving the lessons calendar
+const LESSONS_BASE_URL: &str = "https://unifare.unicam.it";
+/// Links to these hosts are the meeting of lessons held or streamed online
+const MEETING_HOSTS: &[&str] = &["teams.microsoft.com", "zoom.us", "meet.google.com", "webex.com", "meet.jit.si"];
+/// Words of the title, notes and class names telling the kind of a lesson, the first match wins.
+/// A trailing `*` matches every word starting with the rest, so `laborator*` doesn't match `elaboratori`.
+const KIND_MARKERS: &[(LessonKind, &[&str])] = &[
+    (LessonKind::Exam, &["esam*", "appell*", "exam*", "prova", "prove", "verifica"]),
+    (LessonKind::Lab, &["laborator*", "lab", "esercitazion*"]),
+    (LessonKind::Seminar, &["seminari*", "seminar", "webinar", "workshop", "convegn*"]),
+];
+/// Same as `KIND_MARKERS` for the status, cancellations win over changes
+const STATUS_MARKERS: &[(LessonStatus, &[&str])] = &[
+    (LessonStatus::Cancelled, &["annullat*", "sospes*", "soppress*", "cancelled", "canceled"]),
+    (LessonStatus::Moved, &["spostat*", "rinviat*", "posticipat*", "anticipat*", "recuper*", "moved", "rescheduled"]),
+];
+
+pub struct UnicamCrawler {
+    http: HttpClient,
+    courses_base_url: String,
+    lessons_base_url: String,
+    rooms: RoomCatalog,
+}
 
+impl Default for UnicamCrawler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl UnicamCrawler {
+    pub fn new() -> Self {
+        Self::with_base_urls(COURSES_BASE_URL, LESSONS_BASE_URL)
+    }
 
-pub struct UnicamCrawler;
+    /// Crawls other hosts than the university ones (e.g. a fixture server), urls without the trailing slash
+    pub fn with_base_urls(courses_base_url: &str, lessons_base_url: &str) -> Self {
+        Self {
+            http: HttpClient::new(HttpConfig::from_env("unicam", HttpConfig::default())),
+            courses_base_url: courses_base_url.to_string(),
+            lessons_base_url: lessons_base_url.to_string(),
+            rooms: RoomCatalog::from_env("unicam"),
+        }
+    }
 
-#[async_trait]
-impl UniversityCrawler for UnicamCrawler 
-{
-    // ============================================================================================================ 
+    /// Completes the lessons locations with another catalog than the one set in the environment
+    pub fn with_room_catalog(self, rooms: RoomCatalog) -> Self {
+        Self { rooms, ..self }
+    }
 
-    async fn get_lessons(&self, query: HashMap<String, String>) -> Result<Vec<Lesson>, Error> {
+    /// Start of a day in the university timezone, the first instant of it when midnight is skipped by a clock change
+    fn local_midnight(date: NaiveDate) -> DateTime<Tz> {
+        let midnight = date.and_time(NaiveTime::MIN);
+        TIMEZONE
+            .from_local_datetime(&midnight)
+            .earliest()
+            .unwrap_or_else(|| TIMEZONE.from_utc_datetime(&(midnight - TIMEZONE.offset_from_utc_datetime(&midnight).fix())))
+    }
 
-        // Global parameters
-        let mut to_return: Vec<Lesson> = vec![];
-        let date_from: String;
-        let date_to: String;
-        let body: String;
+    /// Splits an option label like `L-31 - Informatica` into course code and name.
+    /// Only the first separator counts, so names containing dashes are kept whole.
+    fn split_course_label(label: &str) -> Option<(&str, &str)> {
+        [" - ", " – ", " — "]
+            .iter()
+            .filter_map(|separator| label.split_once(separator))
+            .min_by_key(|(code, _)| code.len())
+            .map(|(code, name)| (code.trim(), name.trim()))
+    }
+
+    /// Converts an upstream event boundary into an instant.
+    /// Accepts millisecond timestamps, RFC 3339 strings and local (Europe/Rome) date-times.
+    fn parse_timestamp(value: &serde_json::Value) -> Result<DateTime<Utc>, Error> {
+        let parsed = match value {
+            serde_json::Value::Number(millis) => millis
+                .as_i64()
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+
+            serde_json::Value::String(text) => DateTime::parse_from_rfc3339(text)
+                .map(|datetime| datetime.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S")
+                        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S"))
+                        .ok()
+                        .and_then(|naive| TIMEZONE.from_local_datetime(&naive).earliest())
+                        .map(|datetime| datetime.with_timezone(&Utc))
+                }),
+
+            _ => None,
+        };
 
+        parsed.ok_or(Error {
+            error: "Error while parsing crawled data from unicam".into(),
+            http_code: None,
+            message: Some(format!("Invalid lesson timestamp: {}", value)),
+            fault: ErrorFault::External,
+            retry_after: None
+        })
+    }
 
-        // User choosen parameters
-        let course_id = match query.get("course_id") {
-            Some(id) => id,
-            None => return Err(Error {
-                            error: "Bad request".into(),
-                            http_code: Some(400),
-                            message: Some("Missing course_id in query parameters".into()),
-                            fault: ErrorFault::User
-                        }),
+    /// Infers kind and status of an upstream event out of its title, notes and class names.
+    /// The room is left out, lectures are held in laboratories too, and so is the colour, which only tells the courses apart.
+    fn classify(lesson: &serde_json::Value, details: &LessonDetails) -> (LessonKind, LessonStatus) {
+        let class_names: Vec<&str> = match &lesson["className"] {
+            serde_json::Value::String(name) => vec![name.as_str()],
+            serde_json::Value::Array(names) => names.iter().filter_map(|name| name.as_str()).collect(),
+            _ => vec![],
         };
 
-        let course_year = match query.get("course_year") {
-            Some(year) => year,
-            None => return Err(Error {
-                            error: "Bad request".into(),
-                            http_code: Some(400),
-                            message: Some("Missing course_year in query parameters".into()),
-                            fault: ErrorFault::User
-                        }),
+        let words: Vec<String> = std::iter::once(lesson["title"].as_str().unwrap_or(""))
+            .chain(details.notes.iter().map(String::as_str))
+            .chain(class_names)
+            .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+
+        let has_marker = |markers: &[&str]| {
+            markers.iter().any(|marker| match marker.strip_suffix('*') {
+                Some(stem) => words.iter().any(|word| word.starts_with(stem)),
+                None => words.iter().any(|word| word == marker),
+            })
         };
 
+        let kind = KIND_MARKERS
+            .iter()
+            .find(|(_, markers)| has_marker(markers))
+            .map(|(kind, _)| *kind)
+            .unwrap_or_default();
 
-        let weeks: i8 = query        
-                    .get("weeks")
-                    .and_then(|s| s.parse::<i8>().ok()) // try to parse
-                    .unwrap_or(3); // default to 3 weeks if missing or invalid;
+        let status = STATUS_MARKERS
+            .iter()
+            .find(|(_, markers)| has_marker(markers))
+            .map(|(status, _)| *status)
+            .unwrap_or_default();
 
-                    
-        //-----------------------------------------------------------------------------------
+        (kind, status)
+    }
+}
 
+/// What can be read out of the description of a lesson, e.g.
+/// `AULA A - Polo Lodovici <div style="height:8px"></div><b>Docenti:</b> ROSSI MARIO, VERDI ANNA`.
+/// Every section is optional, the text before the first `<b>Label:</b>` is the location.
+#[derive(Debug, Default)]
+struct LessonDetails {
+    room: Option<String>,
+    building: Option<String>,
+    teachers: Vec<String>,
+    notes: Vec<String>,
+    links: Vec<String>,
+    /// `mailto:` links || (text, address)
+    emails: Vec<(String, String)>,
+}
 
-        {//Parse the input fields
+impl LessonDetails {
+    fn parse(description: &str) -> Self {
+        let mut details = LessonDetails::default();
+
+        // Text of every section, one line per block of the markup
+        let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+        Self::collect(&html::parse(description), &mut sections, &mut details);
+
+        for (label, content) in sections {
+            let lines: Vec<String> = content
+                .lines()
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|line| !line.is_empty())
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
 
-            // Valid year from 0 to 5
-            if !Regex::new(r"^[0-5]$").unwrap().is_match(course_year) {
-                return Err(Error {
-                    error: "Bad request".into(),
-                    http_code: Some(400),
-                    message: Some("course_year must be a number from 0 to 5".into()),
-                    fault: ErrorFault::User
-                });
+            match label.as_deref() {
+                None | Some("aula" | "aule" | "sede" | "luogo") if details.room.is_none() => {
+                    // `<room> - <building>`, only the first separator counts
+                    let location = lines.join(", ");
+                    match location.split_once(" - ") {
+                        Some((room, building)) => {
+                            details.room = Some(room.trim().to_string());
+                            details.building = Some(building.trim().to_string());
+                        }
+                        None => details.room = Some(location),
+                    }
+                }
+
+                Some(label) if label.starts_with("docent") => {
+                    details.teachers.extend(
+                        lines
+                            .iter()
+                            .flat_map(|line| line.split([',', ';']))
+                            .map(|teacher| teacher.trim().to_string())
+                            .filter(|teacher| !teacher.is_empty()),
+                    );
+                }
+
+                Some("note" | "annotazioni") | None => details.notes.push(lines.join(" ")),
+
+                // Sections we don't know about are kept along with their label
+                Some(label) => details.notes.push(format!("{}: {}", label, lines.join(" "))),
             }
+        }
 
-            // Validate weeks from 1 to 5
-            if weeks < 1 || weeks > 5 {
-                return Err(Error {
-                    error: "Bad request".into(),
-                    http_code: Some(400),
-                    message: Some("Weeks must be between 1 and 5".into()),
-                    fault: ErrorFault::User
-                });
+        // Links written as plain text
+        for note in &details.notes {
+            for word in note.split_whitespace() {
+                if (word.starts_with("https://") || word.starts_with("http://"))
+                    && !details.links.iter().any(|link| link == word)
+                {
+                    details.links.push(word.to_string());
+                }
             }
+        }
 
+        details
+    }
+
+    /// Walks the markup, starting a new section at every bold label ending with `:`
+    fn collect(element: &Element, sections: &mut Vec<(Option<String>, String)>, details: &mut LessonDetails) {
+        for node in &element.children {
+            match node {
+                Node::Text(text) => sections.last_mut().unwrap().1.push_str(text),
+
+                Node::Element(child) if ["b", "strong"].contains(&child.name.as_str()) && child.text().ends_with(':') => {
+                    let label = child.text().trim_end_matches(':').trim().to_lowercase();
+                    sections.push((Some(label), String::new()));
+                }
+
+                Node::Element(child) => {
+                    if child.name == "a"
+                        && let Some(href) = child.attr("href").map(str::trim).filter(|href| !href.is_empty())
+                    {
+                        match href.strip_prefix("mailto:") {
+                            Some(address) => details.emails.push((child.text(), address.to_string())),
+                            None if !details.links.iter().any(|link| link == href) => details.links.push(href.to_string()),
+                            None => {}
+                        }
+                    }
+
+                    // Blocks and line breaks split the values of a section
+                    sections.last_mut().unwrap().1.push('\n');
+                    Self::collect(child, sections, details);
+                    sections.last_mut().unwrap().1.push('\n');
+                }
+            }
         }
+    }
 
-        //-----------------------------------------------------------------------------------
+    /// Teachers along with the address of the `mailto:` link on their name, if any
+    fn teachers(&self) -> Vec<Teacher> {
+        self.teachers
+            .iter()
+            .map(|name| Teacher {
+                name: name.clone(),
+                email: self
+                    .emails
+                    .iter()
+                    .find(|(text, _)| text.eq_ignore_ascii_case(name))
+                    .map(|(_, address)| address.clone()),
+                id: None,
+            })
+            .collect()
+    }
 
-        {//Set the time span
+    /// Room and building as published, along with the meeting link if the lesson is online
+    fn location(&self) -> Option<Location> {
+        let location = Location {
+            room: self.room.clone(),
+            building: self.building.clone(),
+            online_url: self
+                .links
+                .iter()
+                .find(|link| MEETING_HOSTS.iter().any(|host| link.contains(host)))
+                .cloned(),
+            ..Location::default()
+        };
 
-            let today = Utc::now(); // Just date, no time
+        Some(location).filter(|location| *location != Location::default())
+    }
 
-            // Get the monday of the current week
-            let weekday = today.weekday();
-            let day_since_monday = weekday.num_days_from_monday() as i64;
-            let start = today - Duration::days(day_since_monday);
+    /// Notes followed by the links not already written in them
+    fn description(&self) -> Option<String> {
+        let mut lines = self.notes.clone();
+        lines.extend(
+            self.links
+                .iter()
+                .filter(|link| !self.notes.iter().any(|note| note.contains(link.as_str())))
+                .cloned(),
+        );
+
+        Some(lines.join("\n")).filter(|description| !description.is_empty())
+    }
+}
 
-            // Get the end of the specified period
-            let end = start + Duration::weeks(weeks as i64) - Duration::days(1);
+#[async_trait]
+impl UniversityCrawler for UnicamCrawler 
+{
+    // ============================================================================================================ 
+
+    fn metadata(&self) -> University {
+        University {
+            id: "unicam",
+            name: "Università di Camerino",
+            country: "IT",
+            timezone: TIMEZONE,
+            capabilities: vec![Capability::Lessons, Capability::Courses],
+            parameters: LessonQuery::parameters(COURSE_YEARS),
+        }
+    }
+
+    // ============================================================================================================ 
+
+    async fn get_lessons(&self, query: LessonQuery, range: DateRange) -> Result<Vec<Lesson>, Error> {
+
+        // Global parameters
+        let mut to_return: Vec<Lesson> = vec![];
+        let date_from: String;
+        let date_to: String;
+        let body: String;
+
+
+        // User choosen parameters
+        let course_id = query.course_id.as_str();
+        let course_year = query.course_year.to_string();
+
+
+        //-----------------------------------------------------------------------------------
+
+        {//Set the time span
+
+            // Days are the university ones, and the upstream end date is exclusive, so the day after the last requested one is sent
+            let start = Self::local_midnight(range.from);
+            let end = Self::local_midnight(range.to + Duration::days(1));
 
             date_from = start.to_rfc3339();
             date_to = end.to_rfc3339();
@@ -105,53 +361,27 @@ impl UniversityCrawler for UnicamCrawler
         //-----------------------------------------------------------------------------------
 
         {// Request maker
-            let _request = Client::new()
-                .get("https://unifare.unicam.it//controller/ajaxController.php")
-                .query(&[
-                    ("filename", "../didattica/controller/orari.php"),
-                    ("class", "OrariController"),
-                    ("method", "getDateLezioniByPercorsoCalendar"),
-                    ("parametri[]", course_id),
-                    ("parametri[]", "false"),
-                    ("parametri[]", course_year),
-                    ("start", date_from.as_str()),
-                    ("end", date_to.as_str()),
-                    // ("start", "2025-06-02T14:44:09.523442800+00:00"),   // For testing purposes
-                    // ("end", "2025-06-15T14:44:09.523442800+00:00")      // For testing purposes
-                ]);
-
-            let _response = _request.send().await;
-            
-            
-            // Check if the request was successful
-            match _response {
-                Ok(_response_unwrapped) => {
-
-                    let _status_code = _response_unwrapped.status();
-                    if  _status_code != 200 {
-                        return Err(Error {
-                            error: "Error while crawling lessons from unicam".into(),
-                            http_code: None,
-                            message: Some(format!("The server responded with status code: {}", _status_code)),
-                            fault: ErrorFault::External
-                        });
-                    }
-
-                    // Parse the response
-                    // println!("{:#?}", _result.ok().unwrap().text());
-
-                    body = _response_unwrapped.text().await.unwrap();
-                }
-
-                Err(err) => {
-                    return Err(Error {
-                        error: "Error while crawling lessons from unicam".into(),
-                        http_code: None,
-                        message: Some(format!("Connection error while attempting to fetch: {}", err)),
-                        fault: ErrorFault::Internal
-                    });
-                }
-            }
+            body = self.http
+                .get(
+                    &format!("{}//controller/ajaxController.php", self.lessons_base_url),
+                    &[
+                        ("filename", "../didattica/controller/orari.php"),
+                        ("class", "OrariController"),
+                        ("method", "getDateLezioniByPercorsoCalendar"),
+                        ("parametri[]", course_id),
+                        ("parametri[]", "false"),
+                        ("parametri[]", course_year.as_str()),
+                        ("start", date_from.as_str()),
+                        ("end", date_to.as_str()),
+                        // ("start", "2025-06-02T14:44:09.523442800+00:00"),   // For testing purposes
+                        // ("end", "2025-06-15T14:44:09.523442800+00:00")      // For testing purposes
+                    ],
+                )
+                .await
+                .map_err(|err| Error {
+                    error: "Error while crawling lessons from unicam".into(),
+                    ..err
+                })?;
         }
 
         //-----------------------------------------------------------------------------------
@@ -164,7 +394,8 @@ impl UniversityCrawler for UnicamCrawler
                     error: "Error while parsing crawled data from unicam".into(),
                     http_code: None,
                     message: Some(format!("Parsing error: {:#?} \nRequest query: {:#?}\nFrom: {:#?}\nTo: {:#?} \nBody: {:#?}",error, query, date_from, date_to, body )),
-                    fault: ErrorFault::Internal
+                    fault: ErrorFault::External,
+                    retry_after: None
                 })?;
 
             // println!("JSON: {:#?}", _json);
@@ -172,25 +403,54 @@ impl UniversityCrawler for UnicamCrawler
             if let Some(lessons) = _json.as_array() {
                 for lesson in lessons {
 
-                    let description_string = lesson["description"].to_string();
-                    let description_split = description_string.split(" <div style=\\\"height:8px\\\"></div><b>Docenti:</b> ").collect::<Vec<&str>>();
+                    let details = LessonDetails::parse(lesson["description"].as_str().unwrap_or(""));
+                    let mut location = details.location();
+                    if let Some(location) = location.as_mut() {
+                        self.rooms.complete(location);
+                    }
+
+                    let starts_at = Self::parse_timestamp(&lesson["start"])?;
+                    let ends_at = Self::parse_timestamp(&lesson["end"])?;
+                    let subject = lesson["title"].as_str().unwrap_or("").to_string();
+                    let (kind, status) = Self::classify(lesson, &details);
+
+                    let id = match &lesson["id"] {
+                        // Upstream event identifier
+                        serde_json::Value::String(id) if !id.is_empty() => Lesson::make_id(&["unicam", id]),
+                        serde_json::Value::Number(id) => Lesson::make_id(&["unicam", &id.to_string()]),
+                        // Canonical hash, the subject is normalized so formatting changes keep the same id
+                        _ => Lesson::make_id(&[
+                            "unicam",
+                            course_id,
+                            &course_year,
+                            &starts_at.to_rfc3339(),
+                            &ends_at.to_rfc3339(),
+                            &subject.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect::<String>(),
+                        ]),
+                    };
                     
                     to_return.push(Lesson {
-                        starts_at: lesson["start"].to_string(),
-                        ends_at: lesson["end"].to_string(),
-                        subject: lesson["title"].as_str().unwrap_or("").to_string(),
-                        location: Some(description_split[0].to_string()),
-                        teacher: Some(description_split[1].to_string()),
-                        description: None
+                        id,
+                        starts_at,
+                        ends_at,
+                        timezone: TIMEZONE,
+                        subject,
+                        kind,
+                        status,
+                        location,
+                        teachers: details.teachers(),
+                        description: details.description(),
+                        courses: vec![]
                     })
                 }
             } else {
-                Error {
+                return Err(Error {
                     error: "Error while parsing crawled data from unicam".into(),
                     http_code: None,
                     message: Some(format!("JSON response is not an array \nRequest query: {:#?}\nFrom: {:#?}\nTo: {:#?} \nJson data: {:#?}", query, date_from, date_to, _json)),
-                    fault: ErrorFault::Internal
-                };
+                    fault: ErrorFault::External,
+                    retry_after: None
+                });
             }
 
         }
@@ -202,7 +462,7 @@ impl UniversityCrawler for UnicamCrawler
 
     // ============================================================================================================
     
-    async fn get_courses(&self, _query: HashMap<String, String>) ->  Result<Vec<Course>, Error> {
+    async fn get_courses(&self, _query: CourseQuery) ->  Result<Vec<Course>, Error> {
 
         // Global parameters
         let mut to_return: Vec<Course> = vec![];
@@ -210,69 +470,58 @@ impl UniversityCrawler for UnicamCrawler
 
         {//Request maker
 
-            let _request = Client::new()
-                    .get("https://orarilezioni.unicam.it/");
-            
-            let _response = _request.send().await;
-    
-            match _response {
-                Ok(_response_unwrapped) => {
-    
-                    let _status_code = _response_unwrapped.status();
-                    if  _status_code != 200 {
-                        return Err(Error {
-                            error: "Error while crawling courses from unicam".into(),
-                            http_code: None,
-                            message: Some(format!("The server responded with status code: {}", _status_code)),
-                            fault: ErrorFault::External
-                        });
-                    }
-    
-                    // Parse the response
-                    _html = _response_unwrapped.text().await.unwrap();
-
-                }
-                Err(err) => {
-                    return Err(Error {
-                        error: "Error while crawling data from unicam".into(),
-                        http_code: None,
-                        message: Some(format!("Connection error while attempting to fetch: {}", err)),
-                        fault: ErrorFault::Internal
-                    });
-                }
-            }
+            _html = self.http
+                .get(&format!("{}/", self.courses_base_url), &[])
+                .await
+                .map_err(|err| Error {
+                    error: "Error while crawling courses from unicam".into(),
+                    ..err
+                })?;
         }
 
         //-----------------------------------------------------------------------------------
 
-        {    
-            // Regex per catturare optgroup e il suo contenuto
-            let optgroup_regex = Regex::new(r#"<optgroup\s+label="([^"]+)"[^>]*>([\s\S]*?)</optgroup>"#).unwrap();
-            // Regex per catturare le option all'interno di un optgroup
-            let option_regex = Regex::new(r#"<option(?:\s+[^>]*)?\s+value="(\d+)"[^>]*>([^<]+)</option>"#).unwrap();
-    
-
-            // Iterating over optgroups in the HTML
-            for optgroup_captures in optgroup_regex.captures_iter(_html.as_str()) {
-                let category = optgroup_captures.get(1).unwrap().as_str();
-                let optgroup_content = optgroup_captures.get(2).unwrap().as_str();
+        {//Parsing page
 
-                // println!("\nOptgroup: {}", category);
-                // println!("Content: {:#?}", optgroup_content);
+            let document = html::parse(&_html);
+            let report = |kind: ParseWarningKind, context: String| {
+                ParseWarning { university: "unicam", resource: Capability::Courses, kind, context }.report()
+            };
 
-                // Check if the category is empty
-                if optgroup_content.trim().is_empty() {continue};
-
-
-                // Iterating over options within the optgroup
-                for option_captures in option_regex.captures_iter(optgroup_content) {
-                    let course_id = option_captures.get(1).unwrap().as_str();
-                    let opt_inner_split = option_captures.get(2).unwrap().as_str().trim().split(" - ").collect::<Vec<&str>>();
-                    let course_code = opt_inner_split.get(0).unwrap_or(&"").to_string();
-                    let course_name = opt_inner_split.get(1).unwrap_or(&"").to_string();
+            // Courses are the options of a select, grouped by school
+            let optgroups = document.find_all("optgroup");
+            if optgroups.is_empty() {
+                report(ParseWarningKind::MissingElement, "optgroup".into());
+            }
 
-                    // println!("\tOption:\t{}\t{}\t{}", course_id, course_code, course_name);
+            for optgroup in optgroups {
+                let Some(category) = optgroup.attr("label").map(str::trim).filter(|label| !label.is_empty()) else {
+                    report(ParseWarningKind::UnexpectedContent, format!("optgroup without label: {}", optgroup.text()));
+                    continue;
+                };
 
+                for option in optgroup.find_all("option") {
+                    let label = option.text();
+
+                    let Some(course_id) = option.attr("value").map(str::trim).filter(|value| !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())) else {
+                        report(ParseWarningKind::UnexpectedContent, format!("option without a numeric value: {}", label));
+                        continue;
+                    };
+
+                    let (course_code, course_name) = match Self::split_course_label(&label) {
+                        Some((code, name)) => (code.to_string(), name.to_string()),
+                        None => {
+                            report(ParseWarningKind::UnexpectedContent, format!("option label without a course code: {}", label));
+                            (String::new(), label.clone())
+                        }
+                    };
+
+                    // Anything else published on the option, e.g. data-lingua="EN" -> lingua: EN
+                    let attributes = option.attributes
+                        .iter()
+                        .filter(|(name, _)| !["value", "selected", "disabled"].contains(&name.as_str()))
+                        .map(|(name, value)| (name.strip_prefix("data-").unwrap_or(name).to_string(), value.trim().to_string()))
+                        .collect();
 
                     // Add the course to the return vector
                     to_return.push(Course {
@@ -280,11 +529,21 @@ impl UniversityCrawler for UnicamCrawler
                         id: course_id.to_string(),
                         code: course_code,
                         name: course_name,
+                        attributes,
                     })
-
                 }
             }
 
+            // An empty list would be cached for months, better to fail and keep serving the previous one
+            if to_return.is_empty() {
+                return Err(Error {
+                    error: "Error while parsing crawled data from unicam".into(),
+                    http_code: None,
+                    message: Some("No course found in the page, its layout may have changed".into()),
+                    fault: ErrorFault::External,
+                    retry_after: None
+                });
+            }
         }
 
 
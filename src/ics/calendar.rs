@@ -0,0 +1,252 @@
+// External libraries
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz, TzOffset};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Internal modules
+use super::writer::IcsWriter;
+use crate::models::{lesson::Lesson, lesson_kind::LessonStatus, teacher::Teacher};
+
+/// How often calendar apps should fetch the feed again, so cancelled and moved lessons show up in time
+const REFRESH_INTERVAL: &str = "PT6H";
+
+/// Observances of a timezone over a year, shared by the calendars covering it
+type Observances = Arc<Vec<Observance>>;
+
+/// Observances of every timezone and year described so far, they don't change while running
+static OBSERVANCES: Lazy<Mutex<HashMap<(Tz, i32), Observances>>> = Lazy::new(Default::default);
+
+/// Offsets in effect from `onset` on, `before` is the one they replace
+#[derive(Clone, Copy)]
+struct Observance {
+    onset: DateTime<Utc>,
+    before: TzOffset,
+    after: TzOffset,
+}
+
+/// Builds the calendar of the lessons, alerting `reminders` minutes before each of them.
+/// Times are local to the timezone of the lessons, which is described by a `VTIMEZONE`.
+pub fn lessons_calendar(lessons: &[Lesson], university: &str, reminders: &[u32]) -> String {
+    let now = Utc::now();
+    let mut ics = IcsWriter::new();
+
+    let mut timezones: Vec<Tz> = vec![];
+    for lesson in lessons {
+        if !timezones.contains(&lesson.timezone) {
+            timezones.push(lesson.timezone);
+        }
+    }
+
+    // Calendar properties
+    ics.begin("VCALENDAR");
+    ics.property("VERSION", &[], "2.0");
+    ics.property("PRODID", &[], "-//Timetable//Timetable Calendar//IT");
+    ics.property("CALSCALE", &[], "GREGORIAN");
+    // No METHOD: the feed is a plain calendar to subscribe to, not a scheduling message (RFC 5546),
+    // which would forbid listing the teachers as attendees
+    ics.text("X-WR-CALNAME", &format!("{} Timetable", university.to_uppercase()));
+    ics.text("X-WR-CALDESC", &format!("Lessons timetable for {}", university));
+    if let Some(timezone) = timezones.first() {
+        ics.text("X-WR-TIMEZONE", timezone.name());
+    }
+    ics.property("REFRESH-INTERVAL", &[("VALUE", "DURATION")], REFRESH_INTERVAL);
+    ics.property("X-PUBLISHED-TTL", &[], REFRESH_INTERVAL);
+
+    // Every timezone referenced by the lessons, covering the years of all of them
+    for timezone in timezones {
+        let lessons = lessons.iter().filter(|lesson| lesson.timezone == timezone);
+        let first = lessons.clone().map(|lesson| lesson.starts_at).min().unwrap();
+        let last = lessons.map(|lesson| lesson.ends_at).max().unwrap();
+        write_timezone(
+            &mut ics,
+            timezone,
+            first.with_timezone(&timezone).year(),
+            last.with_timezone(&timezone).year(),
+        );
+    }
+
+    for lesson in lessons {
+        write_lesson(&mut ics, lesson, university, reminders, &now);
+    }
+
+    ics.end("VCALENDAR");
+    ics.finish()
+}
+
+fn write_lesson(ics: &mut IcsWriter, lesson: &Lesson, university: &str, reminders: &[u32], now: &DateTime<Utc>) {
+    let timezone = [("TZID", lesson.timezone.name())];
+
+    ics.begin("VEVENT");
+    ics.text("UID", &format!("timetable-{}-{}", university, lesson.id));
+    ics.property("DTSTAMP", &[], &format_utc_datetime(now));
+    ics.property("DTSTART", &timezone, &format_local_datetime(&lesson.starts_at, lesson.timezone));
+    ics.property("DTEND", &timezone, &format_local_datetime(&lesson.ends_at, lesson.timezone));
+    ics.text("SUMMARY", &lesson.subject);
+    ics.text("DESCRIPTION", lesson.description.as_deref().unwrap_or(""));
+    ics.text("CATEGORIES", lesson.kind.label());
+    if lesson.status == LessonStatus::Cancelled {
+        ics.property("STATUS", &[], "CANCELLED");
+    }
+
+    if let Some(location) = &lesson.location {
+        ics.text("LOCATION", &location.label().unwrap_or_default());
+        if let Some((latitude, longitude)) = location.coordinates() {
+            ics.property("GEO", &[], &format!("{:.6};{:.6}", latitude, longitude));
+        }
+    }
+
+    // Every teacher attends the lesson, organized by the university since events with attendees need an organizer
+    if !lesson.teachers.is_empty() {
+        ics.property(
+            "ORGANIZER",
+            &[("CN", &university.to_uppercase())],
+            &format!("urn:timetable:{}", university),
+        );
+    }
+    for teacher in &lesson.teachers {
+        ics.property(
+            "ATTENDEE",
+            &[("CN", &teacher.name), ("CUTYPE", "INDIVIDUAL"), ("ROLE", "REQ-PARTICIPANT")],
+            &teacher_address(teacher, university),
+        );
+    }
+
+    for minutes in reminders {
+        ics.begin("VALARM");
+        ics.property("ACTION", &[], "DISPLAY");
+        // Display alarms need a description
+        ics.text("DESCRIPTION", if lesson.subject.is_empty() { "Lesson" } else { &lesson.subject });
+        ics.property("TRIGGER", &[], &format!("-PT{}M", minutes));
+        ics.end("VALARM");
+    }
+
+    ics.end("VEVENT");
+}
+
+/// Describes the offsets of the timezone from the start of `first_year` to the end of `last_year`.
+/// The observance in effect on the first January 1 comes first, then one per transition.
+fn write_timezone(ics: &mut IcsWriter, timezone: Tz, first_year: i32, last_year: i32) {
+    ics.begin("VTIMEZONE");
+    ics.property("TZID", &[], timezone.name());
+
+    for year in first_year..=last_year {
+        let observances = year_observances(timezone, year);
+        // Later years carry on from the transitions of the previous one
+        let skip = if year == first_year { 0 } else { 1 };
+        for observance in &observances[skip..] {
+            write_observance(ics, observance);
+        }
+    }
+
+    ics.end("VTIMEZONE");
+}
+
+/// Observance in effect on January 1 of the year, followed by the transitions within it.
+/// Transitions are spotted day by day, then narrowed down to the second.
+fn year_observances(timezone: Tz, year: i32) -> Observances {
+    if let Some(observances) = OBSERVANCES.lock().unwrap().get(&(timezone, year)) {
+        return observances.clone();
+    }
+
+    let offset_at = |instant: DateTime<Utc>| timezone.offset_from_utc_datetime(&instant.naive_utc());
+    let local_midnight = |year: i32| {
+        let midnight = NaiveDate::from_ymd_opt(year, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        midnight - Duration::seconds(offset_at(midnight).fix().local_minus_utc() as i64)
+    };
+    let (start, end) = (local_midnight(year), local_midnight(year + 1));
+
+    let initial = offset_at(start);
+    let mut observances = vec![Observance { onset: start, before: initial, after: initial }];
+
+    let mut day = start;
+    while day < end {
+        let next = (day + Duration::days(1)).min(end);
+        let (before, after) = (offset_at(day), offset_at(next));
+
+        if !same_offset(&before, &after) {
+            let (mut low, mut high) = (day, next);
+            while high - low > Duration::seconds(1) {
+                let middle = low + (high - low) / 2;
+                if same_offset(&offset_at(middle), &before) {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            // A change right on the next January 1 opens the next year instead
+            if high < end {
+                observances.push(Observance { onset: high, before, after });
+            }
+        }
+
+        day = next;
+    }
+
+    let observances = Arc::new(observances);
+    OBSERVANCES.lock().unwrap().insert((timezone, year), observances.clone());
+    observances
+}
+
+/// `STANDARD` or `DAYLIGHT` component, its onset is written in the local time before it
+fn write_observance(ics: &mut IcsWriter, observance: &Observance) {
+    let Observance { onset, before, after } = observance;
+    let component = match after.dst_offset().is_zero() {
+        true => "STANDARD",
+        false => "DAYLIGHT",
+    };
+    let local_onset = onset.naive_utc() + Duration::seconds(before.fix().local_minus_utc() as i64);
+
+    ics.begin(component);
+    ics.property("DTSTART", &[], &local_onset.format("%Y%m%dT%H%M%S").to_string());
+    ics.property("TZOFFSETFROM", &[], &format_utc_offset(before));
+    ics.property("TZOFFSETTO", &[], &format_utc_offset(after));
+    ics.text("TZNAME", after.abbreviation().unwrap_or(""));
+    ics.end(component);
+}
+
+fn same_offset(a: &TzOffset, b: &TzOffset) -> bool {
+    a.fix() == b.fix() && a.dst_offset() == b.dst_offset()
+}
+
+/// UTC offset as `+hhmm`, or `+hhmmss` when it has seconds
+fn format_utc_offset(offset: &TzOffset) -> String {
+    let seconds = offset.fix().local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+
+    let mut formatted = format!("{}{:02}{:02}", sign, seconds / 3600, seconds % 3600 / 60);
+    if seconds % 60 != 0 {
+        formatted.push_str(&format!("{:02}", seconds % 60));
+    }
+    formatted
+}
+
+/// Calendar address of a teacher, a `mailto:` when the email is known
+fn teacher_address(teacher: &Teacher, university: &str) -> String {
+    match &teacher.email {
+        Some(email) => format!("mailto:{}", email),
+        None => format!(
+            "urn:timetable:{}:teacher:{}",
+            university,
+            teacher.id.clone().unwrap_or_else(|| teacher
+                .name
+                .to_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("-"))
+        ),
+    }
+}
+
+/// Converts an instant to ICS UTC format (YYYYMMDDTHHMMSSZ)
+fn format_utc_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Converts an instant to ICS local format (YYYYMMDDTHHMMSS), to be paired with a `TZID`
+fn format_local_datetime(datetime: &DateTime<Utc>, timezone: Tz) -> String {
+    datetime.with_timezone(&timezone).format("%Y%m%dT%H%M%S").to_string()
+}
@@ -0,0 +1,101 @@
+// Low level iCalendar (RFC 5545) serialization: content lines, escaping and folding.
+
+/// Longest content line, in octets, excluding the line break (RFC 5545 §3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Writes an iCalendar object one content line at a time.
+/// Lines are folded to 75 octets and properties with an empty value are left out,
+/// since RFC 5545 has no notion of an empty property and some clients show them anyway.
+#[derive(Debug, Default)]
+pub struct IcsWriter {
+    output: String,
+}
+
+impl IcsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a component, e.g. `VEVENT`
+    pub fn begin(&mut self, component: &str) {
+        self.line(&format!("BEGIN:{}", component));
+    }
+
+    /// Closes a component opened by `begin`
+    pub fn end(&mut self, component: &str) {
+        self.line(&format!("END:{}", component));
+    }
+
+    /// Property with a TEXT value, escaped as required
+    pub fn text(&mut self, name: &str, value: &str) {
+        self.property(name, &[], &escape_text(value));
+    }
+
+    /// Property with parameters and a value already in its iCalendar form (dates, durations, addresses...).
+    /// Parameter values are quoted when needed.
+    pub fn property(&mut self, name: &str, parameters: &[(&str, &str)], value: &str) {
+        if value.is_empty() {
+            return;
+        }
+
+        let mut line = name.to_string();
+        for (parameter, parameter_value) in parameters {
+            line.push_str(&format!(";{}={}", parameter, quote_parameter(parameter_value)));
+        }
+        line.push(':');
+        line.push_str(value);
+
+        self.line(&line);
+    }
+
+    /// The whole object, every line terminated by CRLF
+    pub fn finish(self) -> String {
+        self.output
+    }
+
+    fn line(&mut self, line: &str) {
+        self.output.push_str(&fold(line));
+        self.output.push_str("\r\n");
+    }
+}
+
+/// Escapes a TEXT value (RFC 5545 §3.3.11)
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Quotes a parameter value when it contains separators, double quotes and control characters can't appear at all (RFC 5545 §3.2)
+pub fn quote_parameter(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| *c != '"' && (!c.is_control() || *c == '\t'))
+        .collect();
+
+    if value.contains([':', ';', ',']) || value.contains(char::is_whitespace) {
+        return format!("\"{}\"", value);
+    }
+    value
+}
+
+/// Splits a content line so no physical line exceeds 75 octets, continuation lines start with a space.
+/// Multi-octet characters are never split.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
@@ -0,0 +1,12 @@
+// Explicit returns are the preferred style across the handlers and crawlers
+#![allow(clippy::needless_return)]
+
+// Initialize crates
+pub mod api;
+pub mod cache;
+pub mod crawlers;
+pub mod ics;
+pub mod models;
+pub mod profiles;
+pub mod redis_helper;
+pub mod scheduler;
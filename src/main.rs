@@ -2,11 +2,8 @@
 use dotenv::dotenv;
 use log::info;
 
-// Initialize crates
-mod api;
-mod crawlers;
-mod models;
-mod redis_helper;
+// Internal modules
+use timetable::{api, redis_helper};
 
 fn main() {
     dotenv().ok(); // Load environment variables from .env file
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Course {
@@ -6,4 +9,44 @@ pub struct Course {
     pub code: String,
     pub name: String,
     pub category: String,
+    /// Any other detail the university publishes about the course (e.g. degree type, language, campus)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// A year of a course, written `<course_id>:<course_year>` in urls (e.g. `3042:1`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CourseRef {
+    pub id: String,
+    pub year: u8,
+}
+
+impl Course {
+    /// Version of the serialized shape, bump it whenever a field is added, removed or changes type
+    pub const SCHEMA_VERSION: u32 = 2;
+}
+
+impl FromStr for CourseRef {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (id, year) = value
+            .rsplit_once(':')
+            .ok_or("expected <course_id>:<course_year>")?;
+
+        if id.trim().is_empty() {
+            return Err("course_id must not be empty".into());
+        }
+
+        return Ok(CourseRef {
+            id: id.trim().to_string(),
+            year: year.trim().parse().map_err(|err| format!("invalid course_year: {}", err))?,
+        });
+    }
+}
+
+impl Display for CourseRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.id, self.year)
+    }
 }
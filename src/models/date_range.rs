@@ -0,0 +1,63 @@
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// Internal modules
+use super::error::{Error, ErrorFault};
+
+/// Default number of weeks returned when neither `to` nor `weeks` are provided
+const DEFAULT_WEEKS: u8 = 3;
+/// Longest span (in days) a single request is allowed to cover, `from` and `to` included
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// Inclusive range of days the lessons are requested for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl DateRange {
+    /// Builds the range out of the `from`, `to` and `weeks` query parameters.
+    ///
+    /// - `from` defaults to the monday of the current week in the university `timezone`
+    /// - `to` defaults to `from` plus `weeks` (default 3) weeks
+    pub fn resolve(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        weeks: Option<u8>,
+        timezone: Tz,
+    ) -> Result<Self, Error> {
+        let from = from.unwrap_or_else(|| {
+            // Monday of the current week, as the days are local to the university
+            let today = Utc::now().with_timezone(&timezone).date_naive();
+            today - Duration::days(today.weekday().num_days_from_monday() as i64)
+        });
+
+        let to = to.unwrap_or_else(|| {
+            from + Duration::weeks(weeks.unwrap_or(DEFAULT_WEEKS) as i64) - Duration::days(1)
+        });
+
+        if from > to {
+            return Err(Error {
+                error: "Bad request".into(),
+                http_code: Some(400),
+                message: Some("from must not be after to".into()),
+                fault: ErrorFault::User,
+                retry_after: None,
+            });
+        }
+
+        if (to - from).num_days() >= MAX_RANGE_DAYS {
+            return Err(Error {
+                error: "Bad request".into(),
+                http_code: Some(400),
+                message: Some(format!("The requested range can't be longer than {} days", MAX_RANGE_DAYS)),
+                fault: ErrorFault::User,
+                retry_after: None,
+            });
+        }
+
+        Ok(Self { from, to })
+    }
+}
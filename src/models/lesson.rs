@@ -1,11 +1,51 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
+use super::course::CourseRef;
+use super::lesson_kind::{LessonKind, LessonStatus};
+use super::location::Location;
+use super::teacher::Teacher;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Lesson {
-    pub starts_at: String,
-    pub ends_at: String,
+    /// Deterministic identifier, stable across crawls of the same lesson
+    pub id: String,
+    /// Serialized as RFC 3339
+    pub starts_at: DateTime<Utc>,
+    /// Serialized as RFC 3339
+    pub ends_at: DateTime<Utc>,
+    /// Timezone of the university the lesson was crawled from
+    pub timezone: Tz,
     pub subject: String,
-    pub teacher: Option<String>,
-    pub location: Option<String>,
+    pub kind: LessonKind,
+    pub status: LessonStatus,
+    pub teachers: Vec<Teacher>,
+    pub location: Option<Location>,
     pub description: Option<String>,
+    /// Courses whose timetable includes the lesson, more than one when it is shared.
+    /// Crawlers leave it empty, it is filled in with the crawled course before caching.
+    #[serde(default)]
+    pub courses: Vec<CourseRef>,
+}
+
+impl Lesson {
+    /// Version of the serialized shape, bump it whenever a field is added, removed or changes type
+    pub const SCHEMA_VERSION: u32 = 5;
+
+    /// Builds a lesson identifier out of the given parts.
+    /// Upstream identifiers made of url-safe characters are kept as they are, anything else is hashed.
+    pub fn make_id(parts: &[&str]) -> String {
+        let joined = parts.join("-");
+
+        if !joined.is_empty()
+            && joined
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return joined;
+        }
+
+        format!("{:x}", md5::compute(parts.join("\u{1f}")))
+    }
 }
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// What a lesson is about, crawlers fall back to `Lecture` when nothing tells otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LessonKind {
+    #[default]
+    Lecture,
+    /// Laboratory and practical sessions
+    Lab,
+    /// Written and oral exams, midterms included
+    Exam,
+    Seminar,
+}
+
+/// Whether a lesson still takes place as published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LessonStatus {
+    #[default]
+    Scheduled,
+    /// Moved from its usual time or room, the lesson times are the new ones
+    Moved,
+    Cancelled,
+}
+
+impl LessonKind {
+    /// Name shown in calendars, e.g. as ICS category
+    pub fn label(&self) -> &'static str {
+        match self {
+            LessonKind::Lecture => "Lecture",
+            LessonKind::Lab => "Lab",
+            LessonKind::Exam => "Exam",
+            LessonKind::Seminar => "Seminar",
+        }
+    }
+}
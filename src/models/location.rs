@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a lesson takes place, every part is optional
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub building: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campus: Option<String>,
+    /// Postal address of the building
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// WGS 84 degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// WGS 84 degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// Meeting link of lessons held or streamed online
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub online_url: Option<String>,
+}
+
+impl Location {
+    /// Human readable location, e.g. `AULA A - Polo Lodovici, Via Madonna delle Carceri, Camerino`.
+    /// Online lessons without a room show their meeting link.
+    pub fn label(&self) -> Option<String> {
+        let place = [&self.room, &self.building]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" - ");
+
+        let label = [Some(place), self.address.clone()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if label.is_empty() {
+            return self.online_url.clone();
+        }
+        Some(label)
+    }
+
+    /// Both coordinates, when known
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+}
@@ -1,3 +1,11 @@
 pub mod course;
+pub mod date_range;
 pub mod error;
 pub mod lesson;
+pub mod lesson_kind;
+pub mod location;
+pub mod parse_warning;
+pub mod profile;
+pub mod query;
+pub mod teacher;
+pub mod university;
@@ -0,0 +1,30 @@
+use log::warn;
+use serde::Serialize;
+
+use super::university::Capability;
+
+/// Something unexpected found while parsing an upstream page, usually a sign that its layout changed
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseWarning {
+    pub university: &'static str,
+    pub resource: Capability,
+    pub kind: ParseWarningKind,
+    /// Element or value the warning is about
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseWarningKind {
+    /// An element the parser relies on is not in the page
+    MissingElement,
+    /// An element is there but its content doesn't have the expected shape
+    UnexpectedContent,
+}
+
+impl ParseWarning {
+    /// Logs the warning as a single JSON line, so it can be picked up by log based alerts
+    pub fn report(&self) {
+        warn!("Parse warning: {}", serde_json::to_string(self).unwrap());
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+// Internal modules
+use super::error::{Error, ErrorFault};
+use super::query::{LessonQuery, TimetableQuery};
+use super::university::University;
+
+/// Most reminders a profile can set on each lesson
+pub const MAX_REMINDERS: usize = 5;
+/// Longest reminder, in minutes before the lesson
+pub const MAX_REMINDER_MINUTES: u32 = 7 * 24 * 60;
+
+/// Saved selection of lessons, served at a short url instead of a long query string.
+/// Takes the parameters of the lessons endpoints, e.g.
+/// `{ "university": "unicam", "courses": ["3042:1", "3051:2"], "subjects": ["analisi"], "reminders": [15] }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub university: String,
+    #[serde(flatten)]
+    pub timetable: TimetableQuery,
+    /// Minutes before every lesson the calendar alerts, only used by the ICS export
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<u32>,
+}
+
+impl Profile {
+    /// Checks the whole selection against the `university` it belongs to, so a saved profile can always be rendered.
+    /// Returns the timetable of every course of the profile.
+    pub fn validate(&self, university: &University) -> Result<Vec<LessonQuery>, Error> {
+        if self.reminders.len() > MAX_REMINDERS {
+            return Err(Self::bad_request(&format!(
+                "at most {} reminders can be set",
+                MAX_REMINDERS
+            )));
+        }
+
+        if self.reminders.iter().any(|minutes| *minutes > MAX_REMINDER_MINUTES) {
+            return Err(Self::bad_request(&format!(
+                "reminders must be at most {} minutes before the lesson",
+                MAX_REMINDER_MINUTES
+            )));
+        }
+
+        self.timetable.filter.validate()?;
+
+        let queries = self.timetable.lesson_queries()?;
+        for query in &queries {
+            query.validate()?;
+            university.check_course_year(query.course_year)?;
+            query.date_range(university.timezone)?;
+        }
+
+        Ok(queries)
+    }
+
+    fn bad_request(message: &str) -> Error {
+        Error {
+            error: "Bad request".into(),
+            http_code: Some(400),
+            message: Some(message.into()),
+            fault: ErrorFault::User,
+            retry_after: None,
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+// Internal modules
+use super::course::CourseRef;
+use super::date_range::DateRange;
+use super::error::{Error, ErrorFault};
+use super::lesson::Lesson;
+use super::lesson_kind::LessonKind;
+use super::university::{Capability, ParameterKind, QueryParameter};
+
+/// Most courses a single timetable can merge
+pub const MAX_COURSES: usize = 8;
+
+/// Query parameters accepted by the lessons endpoints, the timetable of one or more courses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimetableQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub course_id: Option<String>,
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub course_year: Option<u8>,
+    /// Further courses merged in, e.g. `courses=3042:2,3051:1`
+    #[serde(default, deserialize_with = "list_from_str", serialize_with = "list_to_str", skip_serializing_if = "Vec::is_empty")]
+    pub courses: Vec<CourseRef>,
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub weeks: Option<u8>,
+    /// Declared before `extra`, so the filters don't end up among the crawler parameters
+    #[serde(flatten)]
+    pub filter: LessonFilter,
+    /// Crawler specific parameters, sorted by name
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// Timetable of a single course, as crawled and cached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonQuery {
+    pub course_id: String,
+    #[serde(deserialize_with = "from_str")]
+    pub course_year: u8,
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub to: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub weeks: Option<u8>,
+    /// Crawler specific parameters, sorted by name
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// Filters applied to the crawled lessons, they change neither what is crawled nor what is cached.
+/// Lists are comma separated in the url, e.g. `subjects=analisi,programmazione`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LessonFilter {
+    /// Parts of the subjects to keep, case insensitive
+    #[serde(default, deserialize_with = "list_from_str", serialize_with = "list_to_str", skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    /// Parts of the subjects to leave out, case insensitive
+    #[serde(default, deserialize_with = "list_from_str", serialize_with = "list_to_str", skip_serializing_if = "Vec::is_empty")]
+    pub exclude_subjects: Vec<String>,
+    /// Part of the name of one of the teachers, case insensitive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teacher: Option<String>,
+    /// Part of the room, building or address, case insensitive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<LessonKind>,
+    /// Days of the week in the university timezone, e.g. `mon,wed`
+    #[serde(default, deserialize_with = "list_from_str", serialize_with = "list_to_str", skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<Weekday>,
+    /// Earliest start, as a time of day in the university timezone
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub after: Option<NaiveTime>,
+    /// Latest end, as a time of day in the university timezone
+    #[serde(default, deserialize_with = "option_from_str", skip_serializing_if = "Option::is_none")]
+    pub before: Option<NaiveTime>,
+}
+
+/// Query parameters accepted by the courses endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CourseQuery {
+    /// Crawler specific parameters, sorted by name
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl TimetableQuery {
+    /// Splits the timetable into one query per course, `course_id`/`course_year` first, without repetitions
+    pub fn lesson_queries(&self) -> Result<Vec<LessonQuery>, Error> {
+        let single = match (&self.course_id, self.course_year) {
+            (Some(id), Some(year)) => Some(CourseRef {
+                id: id.clone(),
+                year,
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(LessonQuery::bad_request(
+                    "course_id and course_year must be given together",
+                ));
+            }
+        };
+
+        let mut courses: Vec<CourseRef> = vec![];
+        for course in single.into_iter().chain(self.courses.iter().cloned()) {
+            if !courses.contains(&course) {
+                courses.push(course);
+            }
+        }
+
+        if courses.is_empty() {
+            return Err(LessonQuery::bad_request(
+                "course_id and course_year, or courses, are required",
+            ));
+        }
+
+        if courses.len() > MAX_COURSES {
+            return Err(LessonQuery::bad_request(&format!(
+                "at most {} courses can be merged",
+                MAX_COURSES
+            )));
+        }
+
+        Ok(courses
+            .into_iter()
+            .map(|course| LessonQuery {
+                course_id: course.id,
+                course_year: course.year,
+                from: self.from,
+                to: self.to,
+                weeks: self.weeks,
+                extra: self.extra.clone(),
+            })
+            .collect())
+    }
+}
+
+impl LessonQuery {
+    /// Checks the parameters shared by every crawler, the years a course can have are up to each of them
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.course_id.trim().is_empty() {
+            return Err(Self::bad_request("course_id must not be empty"));
+        }
+
+        if self.weeks.is_some_and(|weeks| !(1..=5).contains(&weeks)) {
+            return Err(Self::bad_request("weeks must be a number between 1 and 5"));
+        }
+
+        Ok(())
+    }
+
+    /// Describes the parameters shared by every crawler, the ones checked by `validate`.
+    /// `course_years` are the years the courses of the university can have, checked by `University::check_course_year`.
+    pub fn parameters(course_years: RangeInclusive<u8>) -> Vec<QueryParameter> {
+        let parameter = |name, kind, required, description| QueryParameter {
+            name,
+            resource: Capability::Lessons,
+            kind,
+            required,
+            description,
+            min: None,
+            max: None,
+            default: None,
+        };
+
+        vec![
+            parameter(
+                "course_id",
+                ParameterKind::String,
+                false,
+                "Course identifier, as returned by the courses endpoint, required unless courses is given",
+            ),
+            QueryParameter {
+                min: Some(*course_years.start() as i64),
+                max: Some(*course_years.end() as i64),
+                ..parameter(
+                    "course_year",
+                    ParameterKind::Integer,
+                    false,
+                    "Year of the course, required along with course_id",
+                )
+            },
+            parameter(
+                "courses",
+                ParameterKind::String,
+                false,
+                "Further courses merged into the same timetable, as comma separated course_id:course_year pairs (at most 8 courses overall)",
+            ),
+            QueryParameter {
+                default: Some("monday of the current week in the university timezone"),
+                ..parameter(
+                    "from",
+                    ParameterKind::Date,
+                    false,
+                    "First day of the timetable",
+                )
+            },
+            QueryParameter {
+                default: Some("from + weeks - 1 day"),
+                ..parameter(
+                    "to",
+                    ParameterKind::Date,
+                    false,
+                    "Last day of the timetable, at most 365 days after from (366 days overall)",
+                )
+            },
+            QueryParameter {
+                min: Some(1),
+                max: Some(5),
+                default: Some("3"),
+                ..parameter(
+                    "weeks",
+                    ParameterKind::Integer,
+                    false,
+                    "Number of weeks returned when to is missing",
+                )
+            },
+            parameter(
+                "subjects",
+                ParameterKind::String,
+                false,
+                "Only the lessons whose subject contains one of these comma separated texts, case insensitive",
+            ),
+            parameter(
+                "exclude_subjects",
+                ParameterKind::String,
+                false,
+                "Leaves out the lessons whose subject contains one of these comma separated texts, case insensitive",
+            ),
+            parameter(
+                "teacher",
+                ParameterKind::String,
+                false,
+                "Only the lessons held by a teacher whose name contains this text, case insensitive",
+            ),
+            parameter(
+                "room",
+                ParameterKind::String,
+                false,
+                "Only the lessons whose room, building or address contains this text, case insensitive",
+            ),
+            parameter(
+                "kind",
+                ParameterKind::String,
+                false,
+                "Only the lessons of this kind: lecture, lab, exam or seminar",
+            ),
+            parameter(
+                "weekdays",
+                ParameterKind::String,
+                false,
+                "Only the lessons on these comma separated days, e.g. mon,wed,fri",
+            ),
+            parameter(
+                "after",
+                ParameterKind::String,
+                false,
+                "Only the lessons starting at or after this time of day (HH:MM), in the university timezone",
+            ),
+            parameter(
+                "before",
+                ParameterKind::String,
+                false,
+                "Only the lessons ending at or before this time of day (HH:MM), in the university timezone",
+            ),
+        ]
+    }
+
+    /// Resolves the range of days the lessons are requested for, local to the university `timezone`
+    pub fn date_range(&self, timezone: Tz) -> Result<DateRange, Error> {
+        DateRange::resolve(self.from, self.to, self.weeks, timezone)
+    }
+
+    fn bad_request(message: &str) -> Error {
+        Error {
+            error: "Bad request".into(),
+            http_code: Some(400),
+            message: Some(message.into()),
+            fault: ErrorFault::User,
+            retry_after: None,
+        }
+    }
+}
+
+impl LessonFilter {
+    /// Checks the filters, which are left out of the query the crawlers validate
+    pub fn validate(&self) -> Result<(), Error> {
+        if let (Some(after), Some(before)) = (self.after, self.before)
+            && after >= before
+        {
+            return Err(LessonQuery::bad_request("after must be earlier than before"));
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the lessons matching every filter
+    pub fn apply(&self, lessons: Vec<Lesson>) -> Vec<Lesson> {
+        lessons
+            .into_iter()
+            .filter(|lesson| self.matches(lesson))
+            .collect()
+    }
+
+    fn matches(&self, lesson: &Lesson) -> bool {
+        let subject = lesson.subject.to_lowercase();
+        let in_subject = |part: &String| subject.contains(&part.to_lowercase());
+
+        if !self.subjects.is_empty() && !self.subjects.iter().any(in_subject) {
+            return false;
+        }
+
+        if self.exclude_subjects.iter().any(in_subject) {
+            return false;
+        }
+
+        if let Some(teacher) = &self.teacher {
+            let teacher = teacher.trim().to_lowercase();
+            if !lesson
+                .teachers
+                .iter()
+                .any(|t| t.name.to_lowercase().contains(&teacher))
+            {
+                return false;
+            }
+        }
+
+        if let Some(room) = &self.room {
+            let room = room.trim().to_lowercase();
+            let location = lesson.location.as_ref();
+            if !location
+                .into_iter()
+                .flat_map(|location| [&location.room, &location.building, &location.address])
+                .flatten()
+                .any(|part| part.to_lowercase().contains(&room))
+            {
+                return false;
+            }
+        }
+
+        if self.kind.is_some_and(|kind| kind != lesson.kind) {
+            return false;
+        }
+
+        let starts_at = lesson.starts_at.with_timezone(&lesson.timezone);
+        let ends_at = lesson.ends_at.with_timezone(&lesson.timezone);
+
+        if !self.weekdays.is_empty() && !self.weekdays.contains(&starts_at.weekday()) {
+            return false;
+        }
+
+        if self.after.is_some_and(|after| starts_at.time() < after) {
+            return false;
+        }
+
+        if self.before.is_some_and(|before| ends_at.time() > before) {
+            return false;
+        }
+
+        true
+    }
+}
+
+// ================ Deserialization helpers =================
+// Query values reach flattened structs as plain strings, so typed fields are parsed by hand.
+// Saved profiles carry the same parameters in a JSON body, where numbers and arrays are accepted as well.
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawValue {
+    Text(String),
+    Number(i64),
+    List(Vec<RawValue>),
+}
+
+impl RawValue {
+    fn into_text<E: serde::de::Error>(self) -> Result<String, E> {
+        match self {
+            RawValue::Text(text) => Ok(text),
+            RawValue::Number(number) => Ok(number.to_string()),
+            RawValue::List(_) => Err(E::custom("expected a single value")),
+        }
+    }
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = RawValue::deserialize(deserializer)?.into_text::<D::Error>()?;
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|err| serde::de::Error::custom(format!("invalid value '{}': {}", value, err)))
+}
+
+fn option_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    from_str(deserializer).map(Some)
+}
+
+fn list_from_str<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let items: Vec<String> = match RawValue::deserialize(deserializer)? {
+        RawValue::List(items) => items
+            .into_iter()
+            .map(RawValue::into_text::<D::Error>)
+            .collect::<Result<_, _>>()?,
+        value => value
+            .into_text::<D::Error>()?
+            .split(',')
+            .map(String::from)
+            .collect(),
+    };
+
+    items
+        .iter()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse::<T>()
+                .map_err(|err| serde::de::Error::custom(format!("invalid value '{}': {}", item, err)))
+        })
+        .collect()
+}
+
+/// Lists go back to the url the way they came, comma separated
+fn list_to_str<S, T>(list: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    serializer.serialize_str(&list.iter().map(T::to_string).collect::<Vec<_>>().join(","))
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Teacher {
+    /// As published by the university, e.g. `ROSSI MARIO`
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Identifier of the teacher on the university side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
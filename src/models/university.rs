@@ -0,0 +1,109 @@
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// Internal modules
+use super::error::{Error, ErrorFault};
+
+/// Describes a supported university and what its crawler can do
+#[derive(Debug, Clone, Serialize)]
+pub struct University {
+    /// Identifier used in the urls, lowercase
+    pub id: &'static str,
+    pub name: &'static str,
+    /// ISO 3166-1 alpha-2 country code
+    pub country: &'static str,
+    pub timezone: Tz,
+    pub capabilities: Vec<Capability>,
+    pub parameters: Vec<QueryParameter>,
+}
+
+impl University {
+    /// Checks that the crawler specific parameters of a request are all declared for the resource.
+    /// Unknown parameters are rejected, otherwise they would end up in the cache keys and let any caller skip the cache.
+    pub fn check_parameters(&self, resource: Capability, extra: &BTreeMap<String, String>) -> Result<(), Error> {
+        let unknown: Vec<&str> = extra
+            .keys()
+            .map(String::as_str)
+            .filter(|name| {
+                !self
+                    .parameters
+                    .iter()
+                    .any(|parameter| parameter.resource == resource && parameter.name == *name)
+            })
+            .collect();
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error {
+            error: "Bad request".into(),
+            http_code: Some(400),
+            message: Some(format!(
+                "unknown parameters {}, the accepted ones are listed at /timetable/{}",
+                unknown.join(", "),
+                self.id
+            )),
+            fault: ErrorFault::User,
+            retry_after: None,
+        })
+    }
+
+    /// Checks the year of a course against the range declared by the `course_year` parameter
+    pub fn check_course_year(&self, year: u8) -> Result<(), Error> {
+        let Some(parameter) = self
+            .parameters
+            .iter()
+            .find(|parameter| parameter.resource == Capability::Lessons && parameter.name == "course_year")
+        else {
+            return Ok(());
+        };
+
+        let (min, max) = (parameter.min.unwrap_or(i64::MIN), parameter.max.unwrap_or(i64::MAX));
+        if (min..=max).contains(&(year as i64)) {
+            return Ok(());
+        }
+
+        Err(Error {
+            error: "Bad request".into(),
+            http_code: Some(400),
+            message: Some(format!("course_year must be a number from {} to {}", min, max)),
+            fault: ErrorFault::User,
+            retry_after: None,
+        })
+    }
+}
+
+/// Resources a crawler is able to provide
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Lessons,
+    Courses,
+}
+
+/// Query parameter accepted by one of the resources of a crawler
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryParameter {
+    pub name: &'static str,
+    pub resource: Capability,
+    pub kind: ParameterKind,
+    pub required: bool,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterKind {
+    String,
+    Integer,
+    /// ISO 8601 date (YYYY-MM-DD)
+    Date,
+}
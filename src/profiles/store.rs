@@ -0,0 +1,133 @@
+// External libraries
+use log::warn;
+use rand::{Rng, distr::Alphanumeric};
+use redis::{AsyncCommands, RedisResult, aio::ConnectionManager};
+
+// Internal modules
+use crate::models::error::{Error, ErrorFault};
+use crate::models::profile::Profile;
+
+/// Alphanumeric characters of a token, about 95 random bits
+const TOKEN_LENGTH: usize = 16;
+/// Tokens drawn before giving up, another one is only needed when the previous one is taken
+const TOKEN_ATTEMPTS: usize = 3;
+
+/// Profile settings, read from the environment
+///
+/// - `PROFILE_TTL`: seconds a profile is kept after it was last read, `0` keeps it forever (default 180 days).
+///   Calendar apps poll their subscriptions, so a profile in use never expires.
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileConfig {
+    pub ttl: u64,
+}
+
+impl ProfileConfig {
+    pub fn from_env() -> Self {
+        Self {
+            ttl: std::env::var("PROFILE_TTL")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(180 * 60 * 60 * 24),
+        }
+    }
+}
+
+/// Redis backed store of the saved profiles || key -> `profile:<token>`
+pub struct ProfileStore {
+    connection: ConnectionManager,
+    config: ProfileConfig,
+}
+
+impl ProfileStore {
+    pub fn new(connection: ConnectionManager, config: ProfileConfig) -> Self {
+        Self { connection, config }
+    }
+
+    /// Saves the profile under a new random token and returns it.
+    /// Tokens can't be guessed from the selection, saving the same one twice gives two profiles.
+    pub async fn save(&self, profile: &Profile) -> Result<String, Error> {
+        let content = serde_json::to_string(profile).unwrap();
+        let mut connection = self.connection.clone();
+
+        for _ in 0..TOKEN_ATTEMPTS {
+            let token = Self::generate_token();
+
+            let mut command = redis::cmd("SET");
+            command.arg(Self::key(&token)).arg(&content).arg("NX");
+            if self.config.ttl > 0 {
+                command.arg("EX").arg(self.config.ttl);
+            }
+
+            let created: Option<String> = command
+                .query_async(&mut connection)
+                .await
+                .map_err(Self::redis_error)?;
+            if created.is_some() {
+                return Ok(token);
+            }
+        }
+
+        return Err(Error {
+            error: "Internal error".into(),
+            http_code: None,
+            message: Some(format!("No free profile token after {} attempts", TOKEN_ATTEMPTS)),
+            fault: ErrorFault::Internal,
+            retry_after: None,
+        });
+    }
+
+    /// Looks up a profile, keeping it alive for another `PROFILE_TTL`
+    pub async fn get(&self, token: &str) -> Result<Option<Profile>, Error> {
+        let key = Self::key(token);
+        let mut connection = self.connection.clone();
+
+        let content: Option<String> = connection.get(&key).await.map_err(Self::redis_error)?;
+        let Some(content) = content else {
+            return Ok(None);
+        };
+        self.touch(&key).await;
+
+        return serde_json::from_str(&content).map(Some).map_err(|err| Error {
+            error: "Internal error".into(),
+            http_code: None,
+            message: Some(format!("Profile {} can't be decoded: {}", token, err)),
+            fault: ErrorFault::Internal,
+            retry_after: None,
+        });
+    }
+
+    /// Restarts the expiration of a profile, failures are only logged
+    async fn touch(&self, key: &str) {
+        if self.config.ttl == 0 {
+            return;
+        }
+
+        let mut connection = self.connection.clone();
+        let result: RedisResult<()> = connection.expire(key, self.config.ttl as i64).await;
+        if let Err(err) = result {
+            warn!("Failed to refresh the expiration of '{}': {}", key, err);
+        }
+    }
+
+    fn generate_token() -> String {
+        rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect()
+    }
+
+    fn key(token: &str) -> String {
+        format!("profile:{}", token)
+    }
+
+    fn redis_error(err: redis::RedisError) -> Error {
+        Error {
+            error: "Internal error".into(),
+            http_code: None,
+            message: Some(format!("Profile store unavailable: {}", err)),
+            fault: ErrorFault::Internal,
+            retry_after: None,
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use log::info;
-use redis::Client;
+use redis::{Client, aio::ConnectionManager};
 
 pub struct RedisClient {
     pub client: Client,
@@ -31,6 +31,11 @@ impl RedisClient {
         Ok(Self { client })
     }
 
+    /// Opens a multiplexed connection that reconnects automatically, to be shared by async handlers
+    pub async fn connection_manager(&self) -> redis::RedisResult<ConnectionManager> {
+        ConnectionManager::new(self.client.clone()).await
+    }
+
     fn get_connection_string() -> String {
         let credentials = RedisCredentials {
             username: std::env::var("REDIS_USER").ok().or(Some("default".to_string())),
@@ -42,11 +47,11 @@ impl RedisClient {
             db: std::env::var("REDIS_DB").ok().and_then(|d| d.parse().ok()),
         };
 
-        if credentials.username.is_some() && credentials.password.is_some() {
+        if let (Some(username), Some(password)) = (&credentials.username, &credentials.password) {
             format!(
                 "redis://{}:{}@{}:{}/{}",
-                credentials.username.as_ref().unwrap(),
-                credentials.password.as_ref().unwrap(),
+                username,
+                password,
                 credentials.host,
                 credentials.port.unwrap_or(6379),
                 credentials.db.unwrap_or(0)
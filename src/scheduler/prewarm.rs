@@ -0,0 +1,185 @@
+// External libraries
+use actix_web::web::Data;
+use chrono::{Duration, Utc};
+use log::{info, warn};
+use redis::{RedisResult, aio::ConnectionManager};
+use std::time::Duration as StdDuration;
+
+// Internal modules
+use crate::cache::store::Caches;
+use crate::crawlers::store::CrawlerRegistry;
+use crate::models::query::LessonQuery;
+
+/// Pre-warming settings, read from the environment
+///
+/// - `PREWARM_INTERVAL`: seconds between two runs, `0` disables the scheduler (default 900)
+/// - `PREWARM_TOP`: how many of the most requested timetables are kept warm, `0` keeps none (default 50)
+/// - `PREWARM_WINDOW_DAYS`: how many days of requests are considered (default 7)
+#[derive(Debug, Clone, Copy)]
+pub struct PrewarmConfig {
+    pub interval: u64,
+    pub top: usize,
+    pub window_days: i64,
+}
+
+impl PrewarmConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval: std::env::var("PREWARM_INTERVAL")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(900),
+            top: std::env::var("PREWARM_TOP")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(50),
+            window_days: std::env::var("PREWARM_WINDOW_DAYS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|days| *days > 0)
+                .unwrap_or(7),
+        }
+    }
+}
+
+/// Records the requested lessons timetables and keeps the most popular ones warm
+pub struct Prewarmer {
+    connection: ConnectionManager,
+    config: PrewarmConfig,
+}
+
+impl Prewarmer {
+    pub fn new(connection: ConnectionManager, config: PrewarmConfig) -> Self {
+        Self { connection, config }
+    }
+
+    /// Counts a request of the timetables || key -> `prewarm:popularity:<date>`, member -> `["<university>", "<query_string>"]`.
+    /// Written in the background with a single round trip, so the response doesn't wait for it.
+    pub fn record(&self, university: &str, queries: &[LessonQuery]) {
+        if self.config.interval == 0 {
+            return;
+        }
+
+        let key = format!("prewarm:popularity:{}", Utc::now().date_naive());
+
+        let mut pipe = redis::pipe();
+        for query in queries {
+            let Ok(query_string) = serde_urlencoded::to_string(query) else {
+                continue;
+            };
+            let member = serde_json::to_string(&(university, query_string)).unwrap();
+            pipe.zincr(&key, member, 1).ignore();
+        }
+        pipe.expire(&key, self.config.window_days * 60 * 60 * 24).ignore();
+
+        let mut connection = self.connection.clone();
+        actix_web::rt::spawn(async move {
+            let result: RedisResult<()> = pipe.query_async(&mut connection).await;
+            if let Err(err) = result {
+                warn!("Failed to record request popularity: {}", err);
+            }
+        });
+    }
+
+    /// Runs forever, re-crawling the most requested timetables before they expire
+    pub async fn run(
+        prewarmer: Data<Prewarmer>,
+        registry: Data<CrawlerRegistry>,
+        caches: Data<Caches>,
+    ) {
+        if prewarmer.config.interval == 0 {
+            info!("Pre-warming disabled");
+            return;
+        }
+
+        let period = StdDuration::from_secs(prewarmer.config.interval);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+            prewarmer.prewarm(&registry, &caches).await;
+        }
+    }
+
+    /// Single run, returns how many timetables were crawled
+    pub async fn prewarm(&self, registry: &CrawlerRegistry, caches: &Data<Caches>) -> usize {
+        // Entries expiring before the next run are refreshed now
+        let margin = self.config.interval * 2;
+
+        let mut crawled = 0;
+        let popular = self.most_requested().await;
+
+        for (university, query) in &popular {
+            let Some(crawler) = registry.get(university) else {
+                continue;
+            };
+
+            match crawler
+                .prewarm_lessons(university.clone(), query.clone(), caches.clone(), margin)
+                .await
+            {
+                Ok(true) => crawled += 1,
+                Ok(false) => {}
+                Err(err) => warn!(
+                    "Failed to pre-warm {} lessons: {} {}",
+                    university,
+                    err.error,
+                    err.message.unwrap_or_default()
+                ),
+            }
+        }
+
+        info!(
+            "Pre-warming done, {} of the {} most requested timetables crawled",
+            crawled,
+            popular.len()
+        );
+        crawled
+    }
+
+    /// Most requested timetables over the configured window, most popular first
+    pub async fn most_requested(&self) -> Vec<(String, LessonQuery)> {
+        // ZREVRANGE 0 -1 would return all of them
+        if self.config.top == 0 {
+            return vec![];
+        }
+
+        let today = Utc::now().date_naive();
+        let keys: Vec<String> = (0..self.config.window_days)
+            .map(|days| format!("prewarm:popularity:{}", today - Duration::days(days)))
+            .collect();
+
+        let mut connection = self.connection.clone();
+        let result: RedisResult<(Vec<String>,)> = redis::pipe()
+            .atomic()
+            .cmd("ZUNIONSTORE")
+            .arg("prewarm:popularity")
+            .arg(keys.len())
+            .arg(&keys)
+            .ignore()
+            .zrevrange("prewarm:popularity", 0, self.config.top as isize - 1)
+            .del("prewarm:popularity")
+            .ignore()
+            .query_async(&mut connection)
+            .await;
+
+        let members = match result {
+            Ok((members,)) => members,
+            Err(err) => {
+                warn!("Failed to read request popularity: {}", err);
+                return vec![];
+            }
+        };
+
+        // Entries that can't be parsed anymore (e.g. after a query format change) are skipped
+        members
+            .iter()
+            .filter_map(|member| serde_json::from_str::<(String, String)>(member).ok())
+            .filter_map(|(university, query_string)| {
+                serde_urlencoded::from_str::<LessonQuery>(&query_string)
+                    .ok()
+                    .map(|query| (university, query))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,204 @@
+// External libraries
+use futures_util::future::join;
+use std::cell::Cell;
+use std::time::Duration;
+
+// Internal modules
+use timetable::api::errors::error_response;
+use timetable::crawlers::breaker::{BreakerConfig, BreakerState, CircuitBreaker};
+use timetable::models::error::{Error, ErrorFault};
+
+const COOLDOWN: Duration = Duration::from_millis(100);
+
+fn breaker(threshold: u32) -> CircuitBreaker {
+    CircuitBreaker::new(
+        "unicam",
+        BreakerConfig {
+            threshold,
+            cooldown: COOLDOWN,
+        },
+    )
+}
+
+fn error(fault: ErrorFault, http_code: u16) -> Error {
+    Error {
+        error: "Failed".to_string(),
+        message: None,
+        fault,
+        http_code: Some(http_code),
+        retry_after: None,
+    }
+}
+
+/// The university answering with a 502
+fn upstream_failure() -> Error {
+    error(ErrorFault::External, 502)
+}
+
+/// Our rate limiter giving up on the queue
+fn queue_timeout() -> Error {
+    error(ErrorFault::External, 503)
+}
+
+/// Runs a call through the breaker, telling whether it reached the university
+async fn run(breaker: &CircuitBreaker, outcome: Result<(), Error>) -> (bool, Result<(), Error>) {
+    let reached = Cell::new(false);
+    let result = breaker
+        .call(async {
+            reached.set(true);
+            outcome
+        })
+        .await;
+    (reached.get(), result)
+}
+
+async fn open(breaker: &CircuitBreaker) {
+    for _ in 0..3 {
+        let _ = run(breaker, Err(upstream_failure())).await;
+    }
+    assert_eq!(breaker.status().state, BreakerState::Open);
+}
+
+#[actix_web::test]
+async fn opens_after_consecutive_failures_and_fails_fast() {
+    let breaker = breaker(3);
+
+    for failures in 1..3 {
+        let (reached, _) = run(&breaker, Err(upstream_failure())).await;
+        assert!(reached);
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+        assert_eq!(breaker.status().consecutive_failures, failures);
+    }
+
+    let _ = run(&breaker, Err(upstream_failure())).await;
+    assert_eq!(breaker.status().state, BreakerState::Open);
+    assert!(breaker.status().retry_after.is_some());
+
+    // Rejected without calling the university
+    let (reached, result) = run(&breaker, Ok(())).await;
+    assert!(!reached);
+    let error = result.unwrap_err();
+    assert_eq!(error.http_code, Some(503));
+    assert_eq!(error.retry_after, Some(1));
+
+    let response = error_response("unicam", error);
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "1");
+}
+
+#[actix_web::test]
+async fn only_consecutive_upstream_failures_count() {
+    let breaker = breaker(3);
+
+    let _ = run(&breaker, Err(upstream_failure())).await;
+    let _ = run(&breaker, Err(upstream_failure())).await;
+    let _ = run(&breaker, Ok(())).await;
+    assert_eq!(breaker.status().consecutive_failures, 0);
+
+    // Bad requests, internal errors and our own rate limiting don't say anything about the university
+    for _ in 0..5 {
+        let _ = run(&breaker, Err(error(ErrorFault::User, 400))).await;
+        let _ = run(&breaker, Err(error(ErrorFault::Internal, 500))).await;
+        let _ = run(&breaker, Err(queue_timeout())).await;
+    }
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+    assert_eq!(breaker.status().consecutive_failures, 0);
+}
+
+#[actix_web::test]
+async fn closes_when_the_probe_succeeds() {
+    let breaker = breaker(3);
+    open(&breaker).await;
+
+    actix_web::rt::time::sleep(COOLDOWN).await;
+
+    let (reached, result) = run(&breaker, Ok(())).await;
+    assert!(reached && result.is_ok());
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+    assert_eq!(breaker.status().consecutive_failures, 0);
+}
+
+#[actix_web::test]
+async fn reopens_when_the_probe_fails() {
+    let breaker = breaker(3);
+    open(&breaker).await;
+
+    actix_web::rt::time::sleep(COOLDOWN).await;
+
+    let (reached, _) = run(&breaker, Err(upstream_failure())).await;
+    assert!(reached);
+    assert_eq!(breaker.status().state, BreakerState::Open);
+
+    // Another full cooldown before the next probe
+    let (reached, _) = run(&breaker, Ok(())).await;
+    assert!(!reached);
+}
+
+#[actix_web::test]
+async fn lets_a_single_probe_through() {
+    let breaker = breaker(3);
+    open(&breaker).await;
+
+    actix_web::rt::time::sleep(COOLDOWN).await;
+
+    let (probe_tx, probe_rx) = tokio::sync::oneshot::channel::<()>();
+    let probe = breaker.call(async {
+        probe_rx.await.unwrap();
+        Ok(())
+    });
+
+    // Polled while the probe is waiting
+    let other = async {
+        let result = run(&breaker, Ok(())).await;
+        assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+        probe_tx.send(()).unwrap();
+        result
+    };
+
+    let (probe, (reached, other)) = join(probe, other).await;
+    assert!(probe.is_ok());
+    assert!(!reached);
+    assert_eq!(other.unwrap_err().http_code, Some(503));
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
+
+#[actix_web::test]
+async fn closes_when_the_probe_gets_an_answer() {
+    let breaker = breaker(3);
+    open(&breaker).await;
+
+    actix_web::rt::time::sleep(COOLDOWN).await;
+
+    // The university answered, it just didn't like the request
+    let (reached, _) = run(&breaker, Err(error(ErrorFault::User, 404))).await;
+    assert!(reached);
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
+
+#[actix_web::test]
+async fn probes_again_when_the_probe_never_left() {
+    let breaker = breaker(3);
+    open(&breaker).await;
+
+    actix_web::rt::time::sleep(COOLDOWN).await;
+
+    let (reached, _) = run(&breaker, Err(queue_timeout())).await;
+    assert!(reached);
+    assert_eq!(breaker.status().state, BreakerState::Open);
+
+    // No new cooldown, the next request is the probe
+    let (reached, result) = run(&breaker, Ok(())).await;
+    assert!(reached && result.is_ok());
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
+
+#[actix_web::test]
+async fn never_opens_with_a_zero_threshold() {
+    let breaker = breaker(0);
+
+    for _ in 0..10 {
+        let (reached, _) = run(&breaker, Err(upstream_failure())).await;
+        assert!(reached);
+    }
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
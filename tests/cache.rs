@@ -0,0 +1,214 @@
+mod support;
+
+// External libraries
+use actix_web::{
+    body::MessageBody, dev::ServiceResponse, http::StatusCode, rt::time::timeout, test,
+};
+use chrono::Utc;
+use futures_util::future::{join, join_all};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+// Internal modules
+use support::{age, age_of, unicam_app};
+
+const LESSONS_URI: &str =
+    "/timetable/unicam/lessons?course_id=3042&course_year=1&from=2025-03-03&to=2025-03-09";
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+/// Every response carries the freshly crawled lessons
+async fn assert_lessons<B: MessageBody>(responses: Vec<ServiceResponse<B>>) {
+    for response in responses {
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("X-Cache").unwrap(), "MISS");
+        let lessons: Value = test::read_body_json(response).await;
+        assert_eq!(lessons.as_array().unwrap().len(), 4);
+    }
+}
+
+#[actix_web::test]
+async fn evicts_and_refetches_undecodable_entries() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "MISS");
+
+    let keys = unicam.redis.keys("lessons:");
+    assert_eq!(keys.len(), 1);
+
+    // An entry left by an older schema under the current key, then one that isn't JSON at all
+    let payloads = [
+        json!({ "stored_at": Utc::now().timestamp(), "value": [{ "id": "unicam-48211" }] })
+            .to_string(),
+        "{\"stored_at\":".to_string(),
+    ];
+
+    for (failures, payload) in payloads.iter().enumerate() {
+        unicam.redis.set(&keys[0], payload);
+
+        let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("X-Cache").unwrap(), "MISS");
+
+        let lessons: Value = test::read_body_json(response).await;
+        assert_eq!(lessons.as_array().unwrap().len(), 4);
+
+        // Counted, then replaced by the refetched lessons
+        assert_eq!(
+            unicam.redis.get("cache:decode_failures:lessons"),
+            Some((failures + 1).to_string())
+        );
+        assert_eq!(unicam.lessons.requests().len(), failures + 2);
+        assert!(unicam.redis.get(&keys[0]).unwrap().contains("\"subject\""));
+    }
+}
+
+#[actix_web::test]
+async fn serves_stale_entries_while_revalidating() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    test::call_service(&app, request).await;
+    let key = &unicam.redis.keys("lessons:")[0];
+
+    // Past the TTL of 3 days, within the day of stale-while-revalidate
+    age(&unicam.redis, key, 3 * DAY + 12 * HOUR);
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "STALE");
+    assert_eq!(
+        response.headers().get("Warning").unwrap(),
+        "110 - \"Response is Stale\""
+    );
+    let lessons: Value = test::read_body_json(response).await;
+    assert_eq!(lessons.as_array().unwrap().len(), 4);
+
+    // Refreshed in the background
+    for _ in 0..50 {
+        if age_of(&unicam.redis, key) < HOUR {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(age_of(&unicam.redis, key) < HOUR);
+    assert_eq!(unicam.lessons.requests().len(), 2);
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "HIT");
+}
+
+#[actix_web::test]
+async fn serves_stale_entries_when_upstream_fails() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    test::call_service(&app, request).await;
+    let key = &unicam.redis.keys("lessons:")[0];
+
+    unicam.lessons.fail_with(503);
+
+    // Past stale-while-revalidate, the refresh is synchronous and its failure falls back to the stale copy
+    age(&unicam.redis, key, 5 * DAY);
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "STALE");
+    assert_eq!(
+        response.headers().get("Warning").unwrap(),
+        "111 - \"Revalidation Failed\""
+    );
+    let lessons: Value = test::read_body_json(response).await;
+    assert_eq!(lessons.as_array().unwrap().len(), 4);
+    assert!(unicam.lessons.requests().len() > 1);
+
+    // The stale copy is kept for a later attempt
+    assert!(age_of(&unicam.redis, key) >= 5 * DAY);
+
+    // Past stale-if-error (3 + 14 days) the failure reaches the client
+    age(&unicam.redis, key, 13 * DAY);
+
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[actix_web::test]
+async fn coalesces_concurrent_misses_into_one_crawl() {
+    let (app, unicam) = unicam_app().await;
+    unicam.lessons.delay(Duration::from_millis(300));
+
+    // The first request crawls, the others wait for it
+    let responses =
+        join_all((0..8).map(|_| {
+            test::call_service(&app, test::TestRequest::get().uri(LESSONS_URI).to_request())
+        }))
+        .await;
+
+    assert_lessons(responses).await;
+    assert_eq!(unicam.lessons.requests().len(), 1);
+    assert!(unicam.redis.keys("lock:").is_empty());
+}
+
+#[actix_web::test]
+async fn coalesces_concurrent_misses_across_replicas() {
+    let (app, unicam) = unicam_app().await;
+    let replica = unicam.replica().await;
+    unicam.lessons.delay(Duration::from_millis(300));
+
+    // The replica without the Redis lock polls it, then reads the other one's crawl back
+    let (responses, replica_responses) = join(
+        join_all((0..3).map(|_| {
+            test::call_service(&app, test::TestRequest::get().uri(LESSONS_URI).to_request())
+        })),
+        join_all((0..3).map(|_| {
+            test::call_service(
+                &replica,
+                test::TestRequest::get().uri(LESSONS_URI).to_request(),
+            )
+        })),
+    )
+    .await;
+
+    assert_lessons(responses).await;
+    assert_lessons(replica_responses).await;
+    assert_eq!(unicam.lessons.requests().len(), 1);
+    assert!(unicam.redis.keys("lock:").is_empty());
+}
+
+#[actix_web::test]
+async fn releases_the_lock_when_the_crawl_is_cancelled() {
+    let (app, unicam) = unicam_app().await;
+    unicam.lessons.delay(Duration::from_millis(300));
+
+    // The client goes away while its request is crawling
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let cancelled = timeout(
+        Duration::from_millis(100),
+        test::call_service(&app, request),
+    )
+    .await;
+    assert!(cancelled.is_err());
+
+    for _ in 0..50 {
+        if unicam.redis.keys("lock:").is_empty() {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(unicam.redis.keys("lock:").is_empty());
+
+    // The next request crawls right away instead of waiting for the lock to expire
+    let request = test::TestRequest::get().uri(LESSONS_URI).to_request();
+    let response = timeout(Duration::from_secs(2), test::call_service(&app, request))
+        .await
+        .unwrap();
+    assert_lessons(vec![response]).await;
+    assert!(unicam.redis.keys("lock:").is_empty());
+}
@@ -0,0 +1,152 @@
+// Internal modules
+use timetable::crawlers::html::{Element, Node, decode_entities, parse};
+
+/// Names of the child elements, text nodes are skipped
+fn names(element: &Element) -> Vec<&str> {
+    element
+        .elements()
+        .map(|child| child.name.as_str())
+        .collect()
+}
+
+#[test]
+fn keeps_void_elements_empty() {
+    let document = parse("<p>a<br>b<img src=x.png>c<hr/>d</p>");
+
+    let paragraph = document.find_all("p")[0];
+    assert_eq!(names(paragraph), ["br", "img", "hr"]);
+    assert_eq!(paragraph.children.len(), 7);
+    assert!(paragraph.elements().all(|child| child.children.is_empty()));
+    assert_eq!(paragraph.text(), "a b c d");
+}
+
+#[test]
+fn closes_unclosed_elements_like_browsers() {
+    let document = parse(
+        "<select><optgroup label=A><option value=1>One<option value=2>Two\
+         <optgroup label=B><option value=3>Three</select>\
+         <ul><li>a<li>b</ul><table><tr><td>1<td>2<tr><td>3</table>",
+    );
+
+    let select = document.find_all("select")[0];
+    assert_eq!(names(select), ["optgroup", "optgroup"]);
+    let options: Vec<String> = select.find_all("option").iter().map(|o| o.text()).collect();
+    assert_eq!(options, ["One", "Two", "Three"]);
+    assert_eq!(
+        names(select.elements().next().unwrap()),
+        ["option", "option"]
+    );
+
+    assert_eq!(names(document.find_all("ul")[0]), ["li", "li"]);
+    let rows = document.find_all("tr");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(names(rows[0]), ["td", "td"]);
+    assert_eq!(names(rows[1]), ["td"]);
+}
+
+#[test]
+fn closes_the_elements_left_open_by_an_end_tag() {
+    let document = parse("<div><span><b>x</div>y</i><p>z");
+
+    assert_eq!(names(&document), ["div", "p"]);
+    let div = document.find_all("div")[0];
+    assert_eq!(names(div), ["span"]);
+    assert_eq!(div.text(), "x");
+
+    // The stray end tag is ignored, the text after it stays at the top level
+    assert!(matches!(&document.children[1], Node::Text(text) if text == "y"));
+    assert_eq!(document.find_all("p")[0].text(), "z");
+}
+
+#[test]
+fn keeps_raw_text_as_it_is() {
+    let script = "if (a < b && c > d) { html = \"<p>&amp;</p>\"; }";
+    let document = parse(&format!(
+        "<script type=\"text/javascript\">{}</SCRIPT><style>p > a {{ }}</style><p>after</p>",
+        script
+    ));
+
+    assert_eq!(names(&document), ["script", "style", "p"]);
+    assert!(
+        matches!(&document.find_all("script")[0].children[..], [Node::Text(text)] if text == script)
+    );
+    assert_eq!(document.find_all("style")[0].text(), "p > a { }");
+    assert_eq!(document.find_all("p").len(), 1);
+}
+
+#[test]
+fn skips_comments_and_declarations() {
+    let document = parse("<!DOCTYPE html><?xml version=\"1.0\"?><!-- <p>no</p> --><p>yes</p>1 < 2");
+
+    assert_eq!(names(&document), ["p"]);
+    assert_eq!(document.find_all("p")[0].text(), "yes");
+    assert_eq!(document.text(), "yes 1 < 2");
+}
+
+#[test]
+fn decodes_numeric_entities() {
+    assert_eq!(decode_entities("&#232;&#xE8;&#XE8;&#x1F393;"), "èèè🎓");
+
+    // Invalid or out of range code points are left alone
+    assert_eq!(
+        decode_entities("&#xZZ;&#1114112;&#;"),
+        "&#xZZ;&#1114112;&#;"
+    );
+}
+
+#[test]
+fn decodes_named_entities() {
+    assert_eq!(
+        decode_entities("Universit&agrave; &Aacute;&ccedil;&ecirc;&Ntilde;&szlig;&yuml;&THORN;"),
+        "Università ÁçêÑßÿÞ"
+    );
+    assert_eq!(
+        decode_entities("&nbsp;&iexcl;&frac12;&times;&divide;"),
+        "\u{a0}¡½×÷"
+    );
+    assert_eq!(
+        decode_entities("&lt;b&gt; &quot;&apos; &ndash;&hellip;&euro;&trade;&OElig;"),
+        "<b> \"' –…€™Œ"
+    );
+
+    // Decoded once, names are case sensitive and unknown ones are kept
+    assert_eq!(decode_entities("&amp;lt;"), "&lt;");
+    assert_eq!(decode_entities("&EACUTE; &eacute;"), "&EACUTE; é");
+    assert_eq!(decode_entities("&unknown; a & b &"), "&unknown; a & b &");
+}
+
+#[test]
+fn decodes_entities_in_text_and_attributes() {
+    let document =
+        parse("<a title=\"Universit&agrave; &amp; Co\">Propriet&agrave; &#8211; test</a>");
+
+    let link = document.find_all("a")[0];
+    assert_eq!(link.attr("title"), Some("Università & Co"));
+    assert_eq!(link.text(), "Proprietà – test");
+}
+
+#[test]
+fn tolerates_malformed_attributes() {
+    let document = parse(
+        "<a HREF='first' href=\"second\" data-id=42 disabled class=\"x\"id=y title = \"spaced\">link</a>\
+         <input value=\"unterminated>",
+    );
+
+    let link = document.find_all("a")[0];
+    assert_eq!(
+        link.attributes,
+        [
+            ("href".to_string(), "first".to_string()),
+            ("data-id".to_string(), "42".to_string()),
+            ("disabled".to_string(), String::new()),
+            ("class".to_string(), "x".to_string()),
+            ("id".to_string(), "y".to_string()),
+            ("title".to_string(), "spaced".to_string()),
+        ]
+    );
+    assert_eq!(link.text(), "link");
+
+    // The unterminated value runs to the end of the page
+    let input = document.find_all("input")[0];
+    assert_eq!(input.attr("value"), Some("unterminated>"));
+}
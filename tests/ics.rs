@@ -0,0 +1,231 @@
+mod support;
+
+// External libraries
+use chrono::{Datelike, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Internal modules
+use support::ics::{unfold, validate};
+use timetable::ics::{
+    calendar::lessons_calendar,
+    writer::{IcsWriter, escape_text, fold, quote_parameter},
+};
+use timetable::models::{
+    lesson::Lesson,
+    lesson_kind::{LessonKind, LessonStatus},
+    location::Location,
+    teacher::Teacher,
+};
+
+fn lesson(id: &str, starts_at: (u32, u32, u32), hours: i64, timezone: Tz) -> Lesson {
+    let (month, day, hour) = starts_at;
+    let starts_at = Utc.with_ymd_and_hms(2025, month, day, hour, 0, 0).unwrap();
+
+    Lesson {
+        id: id.into(),
+        starts_at,
+        ends_at: starts_at + chrono::Duration::hours(hours),
+        timezone,
+        subject: "PROGRAMMAZIONE".into(),
+        kind: LessonKind::Lecture,
+        status: LessonStatus::Scheduled,
+        teachers: vec![],
+        location: None,
+        description: None,
+        courses: vec![],
+    }
+}
+
+#[test]
+fn folds_lines_at_75_octets() {
+    let line = format!("DESCRIPTION:{}", "a".repeat(200));
+    let folded = fold(&line);
+
+    let lines: Vec<&str> = folded.split("\r\n").collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].len(), 75);
+    assert!(lines[1].starts_with(' ') && lines[1].len() == 75);
+    assert_eq!(unfold(&folded), line);
+
+    // Short lines are left alone
+    assert_eq!(fold("SUMMARY:Analisi"), "SUMMARY:Analisi");
+}
+
+#[test]
+fn never_splits_multi_octet_characters() {
+    let line = format!("SUMMARY:{}", "Università è ".repeat(20));
+    let folded = fold(&line);
+
+    for physical in folded.split("\r\n") {
+        assert!(physical.len() <= 75);
+    }
+    assert_eq!(unfold(&folded), line);
+}
+
+#[test]
+fn escapes_text_and_parameters() {
+    assert_eq!(
+        escape_text("Aula A, piano 1; lato \\nord\nsecondo"),
+        "Aula A\\, piano 1\\; lato \\\\nord\\nsecondo"
+    );
+    assert_eq!(escape_text("a\r\nb"), "a\\nb");
+
+    assert_eq!(quote_parameter("INDIVIDUAL"), "INDIVIDUAL");
+    assert_eq!(quote_parameter("ROSSI, MARIO"), "\"ROSSI, MARIO\"");
+    assert_eq!(quote_parameter("mailto:a@b.it"), "\"mailto:a@b.it\"");
+    assert_eq!(quote_parameter("\"Mario\""), "Mario");
+}
+
+#[test]
+fn omits_empty_properties() {
+    let mut ics = IcsWriter::new();
+    ics.begin("VEVENT");
+    ics.text("DESCRIPTION", "");
+    ics.property("LOCATION", &[("ALTREP", "x")], "");
+    ics.text("SUMMARY", "Analisi");
+    ics.end("VEVENT");
+
+    assert_eq!(
+        ics.finish(),
+        "BEGIN:VEVENT\r\nSUMMARY:Analisi\r\nEND:VEVENT\r\n"
+    );
+}
+
+#[test]
+fn describes_daylight_saving_time_changes() {
+    let rome = chrono_tz::Europe::Rome;
+    // Before and after the change of 30 March 2025
+    let lessons = vec![
+        lesson("before", (3, 28, 8), 2, rome),
+        lesson("after", (3, 31, 7), 2, rome),
+    ];
+
+    let ics = lessons_calendar(&lessons, "unicam", &[]);
+    validate(&ics);
+    let ics = unfold(&ics);
+
+    assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 1);
+    assert!(ics.contains("X-WR-TIMEZONE:Europe/Rome\r\n"));
+    assert!(ics.contains(
+        "BEGIN:DAYLIGHT\r\nDTSTART:20250330T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\nEND:DAYLIGHT\r\n"
+    ));
+    // The offsets in effect on January 1 cover the first lesson, the rest of the year follows
+    assert!(ics.contains(
+        "BEGIN:STANDARD\r\nDTSTART:20250101T000000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\nEND:STANDARD\r\n"
+    ));
+    assert!(ics.contains(
+        "BEGIN:STANDARD\r\nDTSTART:20251026T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\nEND:STANDARD\r\n"
+    ));
+    assert_eq!(ics.matches("BEGIN:STANDARD").count(), 2);
+    assert_eq!(ics.matches("BEGIN:DAYLIGHT").count(), 1);
+
+    // Same wall clock time on both sides of the change
+    assert!(ics.contains("DTSTART;TZID=Europe/Rome:20250328T090000\r\n"));
+    assert!(ics.contains("DTSTART;TZID=Europe/Rome:20250331T090000\r\n"));
+}
+
+#[test]
+fn describes_every_year_of_the_lessons() {
+    let rome = chrono_tz::Europe::Rome;
+    // From December 2024 to January 2025
+    let mut december = lesson("december", (12, 16, 8), 2, rome);
+    december.starts_at = december.starts_at.with_year(2024).unwrap();
+    december.ends_at = december.ends_at.with_year(2024).unwrap();
+    let lessons = vec![december, lesson("january", (1, 13, 8), 2, rome)];
+
+    let ics = lessons_calendar(&lessons, "unicam", &[]);
+    validate(&ics);
+    let ics = unfold(&ics);
+
+    // Only the first January 1 is described, the second one follows from the changes of the year before
+    let onsets: Vec<&str> = ics
+        .lines()
+        .filter(|line| line.starts_with("DTSTART:"))
+        .collect();
+    assert_eq!(
+        onsets,
+        [
+            "DTSTART:20240101T000000",
+            "DTSTART:20240331T020000",
+            "DTSTART:20241027T030000",
+            "DTSTART:20250330T020000",
+            "DTSTART:20251026T030000",
+        ]
+    );
+}
+
+#[test]
+fn describes_timezones_without_daylight_saving_time() {
+    let lessons = vec![lesson("tokyo", (6, 2, 0), 1, chrono_tz::Asia::Tokyo)];
+
+    let ics = lessons_calendar(&lessons, "todai", &[]);
+    validate(&ics);
+
+    assert!(!ics.contains("BEGIN:DAYLIGHT"));
+    assert_eq!(ics.matches("BEGIN:STANDARD").count(), 1);
+    assert!(ics.contains("TZOFFSETFROM:+0900\r\nTZOFFSETTO:+0900\r\n"));
+    assert!(ics.contains("DTSTART;TZID=Asia/Tokyo:20250602T090000\r\n"));
+}
+
+#[test]
+fn writes_valid_lessons() {
+    let mut full = lesson("full", (3, 3, 8), 2, chrono_tz::Europe::Rome);
+    full.subject =
+        "ARCHITETTURA DEGLI ELABORATORI, MODULO 1; TEORIA E LABORATORIO DI PROGRAMMAZIONE".into();
+    full.description = Some(format!("Note:\n{}", "Lezione in streaming ".repeat(10)));
+    full.kind = LessonKind::Lab;
+    full.status = LessonStatus::Cancelled;
+    full.teachers = vec![
+        Teacher {
+            name: "ROSSI MARIO".into(),
+            email: Some("mario.rossi@unicam.it".into()),
+            id: None,
+        },
+        Teacher {
+            name: "D'ANGELO MARIA LUISA, \"MALU\"".into(),
+            email: None,
+            id: None,
+        },
+    ];
+    full.location = Some(Location {
+        room: Some("AULA A".into()),
+        building: Some("Polo Lodovici".into()),
+        address: Some("Via Madonna delle Carceri 9, Camerino".into()),
+        latitude: Some(43.139),
+        longitude: Some(13.0687),
+        ..Location::default()
+    });
+    let mut empty = lesson("empty", (3, 4, 8), 2, chrono_tz::Europe::Rome);
+    empty.subject = String::new();
+
+    let ics = lessons_calendar(&[full, empty], "unicam", &[10, 60]);
+    validate(&ics);
+    let ics = unfold(&ics);
+
+    assert!(ics.contains(
+        "SUMMARY:ARCHITETTURA DEGLI ELABORATORI\\, MODULO 1\\; TEORIA E LABORATORIO DI PROGRAMMAZIONE\r\n"
+    ));
+    assert!(ics.contains("CATEGORIES:Lab\r\nSTATUS:CANCELLED\r\n"));
+    assert!(ics.contains("GEO:43.139000;13.068700\r\n"));
+    assert!(ics.contains(
+        "ATTENDEE;CN=\"D'ANGELO MARIA LUISA, MALU\";CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT:urn:timetable:unicam:teacher:d-angelo-maria-luisa-malu\r\n"
+    ));
+    assert_eq!(ics.matches("BEGIN:VALARM").count(), 4);
+    assert!(ics.contains("TRIGGER:-PT60M\r\n"));
+
+    // The lesson without subject, description, location or teachers
+    let empty = ics.split("BEGIN:VEVENT").nth(2).unwrap();
+    assert!(!empty.contains("SUMMARY"));
+    assert!(!empty.contains("LOCATION"));
+    assert!(empty.contains("DESCRIPTION:Lesson\r\n"));
+}
+
+#[test]
+fn writes_a_valid_empty_calendar() {
+    let ics = lessons_calendar(&[], "unicam", &[15]);
+    validate(&ics);
+
+    assert!(!ics.contains("VTIMEZONE"));
+    assert!(!ics.contains("VEVENT"));
+    assert!(ics.contains("REFRESH-INTERVAL;VALUE=DURATION:PT6H\r\n"));
+}
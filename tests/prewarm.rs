@@ -0,0 +1,136 @@
+mod support;
+
+// External libraries
+use actix_web::{test, web::Data};
+use std::time::Duration;
+
+// Internal modules
+use support::{age, age_of, unicam_app};
+use timetable::cache::store::Caches;
+use timetable::scheduler::prewarm::{PrewarmConfig, Prewarmer};
+
+const FROM_TO: &str = "from=2025-03-03&to=2025-03-09";
+const DAY: i64 = 24 * 60 * 60;
+
+#[actix_web::test]
+async fn keeps_the_most_requested_timetables_warm() {
+    let (app, unicam) = unicam_app().await;
+
+    // Course 3042 is requested twice, once merged with 3051
+    for courses in ["courses=3042:1", "courses=3042:1,3051:1"] {
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/timetable/unicam/lessons?{}&{}",
+                courses, FROM_TO
+            ))
+            .to_request();
+        test::call_service(&app, request).await;
+    }
+
+    let prewarmer = Prewarmer::new(
+        unicam.redis.connection().await,
+        PrewarmConfig {
+            interval: 900,
+            top: 1,
+            window_days: 7,
+        },
+    );
+
+    // Popularity is recorded in the background
+    let mut popular = vec![];
+    for _ in 0..50 {
+        popular = prewarmer.most_requested().await;
+        if !popular.is_empty() {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(popular.len(), 1);
+    assert_eq!(popular[0].0, "unicam");
+    assert_eq!(popular[0].1.course_id, "3042");
+
+    let registry = unicam.registry();
+    let caches = Data::new(Caches::new(unicam.redis.connection().await));
+
+    // Fresh entries are left alone
+    assert_eq!(prewarmer.prewarm(&registry, &caches).await, 0);
+    assert_eq!(unicam.lessons.requests().len(), 2);
+
+    // Entries expiring before the next run are crawled again, only for the most requested course
+    let keys = unicam.redis.keys("lessons:");
+    assert_eq!(keys.len(), 2);
+    for key in &keys {
+        age(&unicam.redis, key, 3 * DAY - 600);
+    }
+
+    assert_eq!(prewarmer.prewarm(&registry, &caches).await, 1);
+    let requests = unicam.lessons.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].contains("parametri%5B%5D=3042"));
+    assert_eq!(
+        keys.iter()
+            .filter(|key| age_of(&unicam.redis, key) < 600)
+            .count(),
+        1
+    );
+}
+
+#[actix_web::test]
+async fn records_nothing_when_disabled() {
+    let (_app, unicam) = unicam_app().await;
+
+    let prewarmer = Prewarmer::new(
+        unicam.redis.connection().await,
+        PrewarmConfig {
+            interval: 0,
+            top: 50,
+            window_days: 7,
+        },
+    );
+    let query =
+        serde_urlencoded::from_str(&format!("course_id=3042&course_year=1&{}", FROM_TO)).unwrap();
+    prewarmer.record("unicam", &[query]);
+
+    actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+    assert!(unicam.redis.keys("prewarm:").is_empty());
+}
+
+#[actix_web::test]
+async fn keeps_nothing_warm_with_a_zero_top() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons?courses=3042:1&{}",
+            FROM_TO
+        ))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let prewarmer = Prewarmer::new(
+        unicam.redis.connection().await,
+        PrewarmConfig {
+            interval: 900,
+            top: 0,
+            window_days: 7,
+        },
+    );
+
+    // Popularity is recorded in the background
+    for _ in 0..50 {
+        if !unicam.redis.keys("prewarm:").is_empty() {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(!unicam.redis.keys("prewarm:").is_empty());
+    assert!(prewarmer.most_requested().await.is_empty());
+
+    for key in unicam.redis.keys("lessons:") {
+        age(&unicam.redis, &key, 3 * DAY - 600);
+    }
+    let registry = unicam.registry();
+    let caches = Data::new(Caches::new(unicam.redis.connection().await));
+    assert_eq!(prewarmer.prewarm(&registry, &caches).await, 0);
+    assert_eq!(unicam.lessons.requests().len(), 1);
+}
@@ -0,0 +1,107 @@
+mod support;
+
+// External libraries
+use std::time::{Duration, Instant};
+
+// Internal modules
+use support::fixtures::FixtureServer;
+use timetable::crawlers::{
+    http::{HttpClient, HttpConfig},
+    rate_limit::HostLimiter,
+};
+
+#[actix_web::test]
+async fn spaces_requests_once_the_burst_is_spent() {
+    // 20 requests per second, 2 at once
+    let limiter = HostLimiter::new(20.0, 2, 10);
+    let started = Instant::now();
+
+    let mut sent = vec![];
+    for _ in 0..6 {
+        let _permit = limiter.acquire(Duration::from_secs(1)).await.unwrap();
+        sent.push(started.elapsed());
+    }
+
+    // The burst goes out right away, then one request every 50ms
+    assert!(sent[1] < Duration::from_millis(20));
+    for pair in sent[2..].windows(2) {
+        assert!(pair[1] - pair[0] >= Duration::from_millis(45), "{:?}", sent);
+    }
+    assert!(sent[5] >= Duration::from_millis(190), "{:?}", sent);
+}
+
+#[actix_web::test]
+async fn caps_the_requests_in_flight() {
+    let limiter = HostLimiter::new(0.0, 1, 2);
+
+    let first = limiter.acquire(Duration::from_millis(50)).await;
+    let second = limiter.acquire(Duration::from_millis(50)).await;
+    assert!(first.is_some() && second.is_some());
+
+    // Both slots are taken until one of them is released
+    let started = Instant::now();
+    assert!(limiter.acquire(Duration::from_millis(100)).await.is_none());
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    drop(first);
+    assert!(limiter.acquire(Duration::from_millis(50)).await.is_some());
+}
+
+#[actix_web::test]
+async fn gives_up_when_the_next_token_is_past_the_timeout() {
+    let limiter = HostLimiter::new(1.0, 1, 10);
+    assert!(limiter.acquire(Duration::from_millis(50)).await.is_some());
+
+    // The next token is a second away, no point in waiting for it
+    let started = Instant::now();
+    assert!(limiter.acquire(Duration::from_millis(100)).await.is_none());
+    assert!(started.elapsed() < Duration::from_millis(50));
+}
+
+#[actix_web::test]
+async fn fails_queued_requests_with_503_instead_of_hanging() {
+    let server = FixtureServer::start("unicam", "orarilezioni.unicam.it").await;
+    let client = HttpClient::new(HttpConfig {
+        rate_limit: 1.0,
+        burst: 1,
+        queue_timeout: Duration::from_millis(100),
+        ..HttpConfig::default()
+    });
+
+    let url = format!("{}/", server.base_url);
+    assert!(client.get(&url, &[]).await.is_ok());
+
+    let started = Instant::now();
+    let error = client.get(&url, &[]).await.unwrap_err();
+    assert_eq!(error.http_code, Some(503));
+    assert!(started.elapsed() < Duration::from_millis(500));
+    assert_eq!(server.requests().len(), 1);
+
+    // Other hosts have a bucket of their own
+    let other_host = url.replace("127.0.0.1", "localhost");
+    assert!(client.get(&other_host, &[]).await.is_ok());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[actix_web::test]
+async fn caps_the_backoff_at_the_timeout() {
+    let server = FixtureServer::start("unicam", "orarilezioni.unicam.it").await;
+    server.fail_with(502);
+    let client = HttpClient::new(HttpConfig {
+        timeout: Duration::from_millis(10),
+        retries: 40,
+        backoff: Duration::from_secs(1),
+        rate_limit: 0.0,
+        ..HttpConfig::default()
+    });
+
+    // Doubling the backoff 40 times would overflow, and each retry would wait longer than the last
+    let started = Instant::now();
+    let error = client
+        .get(&format!("{}/", server.base_url), &[])
+        .await
+        .unwrap_err();
+    assert!(error.message.unwrap().contains("502"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(server.requests().len(), 41);
+}
@@ -0,0 +1,168 @@
+// External libraries
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+// Internal modules
+use timetable::crawlers::{main::UniversityCrawler, recorder::Exchange, unicam::UnicamCrawler};
+use timetable::models::{
+    date_range::DateRange,
+    query::{CourseQuery, LessonQuery},
+};
+
+/// Fixture files and the exchange each one holds: the host, then the course of the lessons
+const UNICAM_FIXTURES: &[(&str, &str, Option<&str>)] = &[
+    ("courses.json", "orarilezioni.unicam.it", None),
+    ("lessons.json", "unifare.unicam.it", Some("3042")),
+    ("lessons-3051.json", "unifare.unicam.it", Some("3051")),
+];
+
+/// Crawls the Unicam hosts and replaces `tests/fixtures/unicam` with what they answered.
+/// It needs the network, so it only runs when asked for:
+///
+/// `CRAWLER_RECORD_DIR=target/recorded cargo test --test record -- --ignored`
+///
+/// Before anything is written, the answers are checked against what the crawler and the fixtures assume
+/// of the upstream format, see `check_lessons_shape`.
+/// The assertions of `tests/unicam.rs` follow the recorded subjects, teachers and rooms,
+/// so they have to be checked against the new fixtures afterwards.
+#[actix_web::test]
+#[ignore = "crawls the real university hosts"]
+async fn records_the_unicam_fixtures() {
+    let record_dir = PathBuf::from(
+        std::env::var("CRAWLER_RECORD_DIR").expect("CRAWLER_RECORD_DIR has to be set"),
+    )
+    .join("unicam");
+    let _ = std::fs::remove_dir_all(&record_dir);
+
+    let crawler = UnicamCrawler::new();
+    let range = DateRange {
+        from: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+        to: NaiveDate::from_ymd_opt(2025, 3, 9).unwrap(),
+    };
+
+    crawler.get_courses(CourseQuery::default()).await.unwrap();
+    for course_id in ["3042", "3051"] {
+        let query = LessonQuery {
+            course_id: course_id.to_string(),
+            course_year: 1,
+            from: Some(range.from),
+            to: Some(range.to),
+            weeks: None,
+            extra: Default::default(),
+        };
+        crawler.get_lessons(query, range).await.unwrap();
+    }
+
+    let recorded: Vec<Exchange> = std::fs::read_dir(&record_dir)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .map(|content| serde_json::from_str(&content).unwrap())
+        .collect();
+
+    let fixtures_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unicam");
+    let mut exchanges = vec![];
+    for (file, host, course_id) in UNICAM_FIXTURES {
+        let exchange = recorded
+            .iter()
+            .find(|exchange| {
+                reqwest::Url::parse(&exchange.url).unwrap().host_str() == Some(host)
+                    && course_id.is_none_or(|course_id| {
+                        exchange
+                            .query
+                            .iter()
+                            .any(|(name, value)| name == "parametri[]" && value == course_id)
+                    })
+            })
+            .unwrap_or_else(|| panic!("Nothing recorded for {}", file));
+        assert_eq!(
+            exchange.status, 200,
+            "{} answered {}",
+            host, exchange.status
+        );
+        exchanges.push((file, exchange));
+    }
+
+    check_lessons_shape(
+        &exchanges
+            .iter()
+            .filter(|(file, _)| file.starts_with("lessons"))
+            .map(|(_, exchange)| serde_json::from_str(&exchange.body).unwrap())
+            .collect::<Vec<Value>>(),
+    );
+
+    for (file, exchange) in exchanges {
+        std::fs::write(
+            fixtures_dir.join(file),
+            serde_json::to_string_pretty(exchange).unwrap() + "\n",
+        )
+        .unwrap();
+    }
+}
+
+/// Fails on the upstream events the crawler and the hand-written fixtures don't expect:
+/// - `start` and `end` are date-times, local ones without an offset or RFC 3339 ones
+/// - `id` is a string, and an event listed by two courses has the same `id`, which the timetables dedupe on
+/// - teachers come after a `<b>Docenti:</b>` label in the description
+///
+/// The colours and class names found are printed, none of them is given a meaning yet.
+fn check_lessons_shape(courses: &[Value]) {
+    let mut by_id: HashMap<&str, &Value> = HashMap::new();
+    let mut colours: BTreeSet<&str> = BTreeSet::new();
+    let mut class_names: BTreeSet<String> = BTreeSet::new();
+
+    for event in courses.iter().flat_map(|events| events.as_array().unwrap()) {
+        let id = event["id"]
+            .as_str()
+            .unwrap_or_else(|| panic!("id is not a string: {}", event));
+
+        for field in ["start", "end"] {
+            let value = event[field].as_str().unwrap_or_default();
+            assert!(
+                NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+                    || DateTime::parse_from_rfc3339(value).is_ok(),
+                "unexpected {}: {}",
+                field,
+                event
+            );
+        }
+
+        let description = event["description"].as_str().unwrap_or_default();
+        if description.to_lowercase().contains("docent") {
+            assert!(
+                description.contains("<b>Docenti:</b>") || description.contains("<b>Docente:</b>"),
+                "unexpected teachers markup: {}",
+                description
+            );
+        }
+
+        // The same lesson listed by both courses
+        if let Some(other) = by_id.insert(id, event) {
+            for field in ["start", "end", "title"] {
+                assert_eq!(other[field], event[field], "id {} is not unique", id);
+            }
+        }
+
+        colours.extend(event["color"].as_str());
+        class_names.insert(event["className"].to_string());
+    }
+
+    println!("Colours: {:?}", colours);
+    println!("Class names: {:?}", class_names);
+}
+
+#[test]
+fn fixtures_match_the_upstream_shape() {
+    let fixtures_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unicam");
+
+    let courses: Vec<Value> = UNICAM_FIXTURES
+        .iter()
+        .filter(|(_, _, course_id)| course_id.is_some())
+        .map(|(file, _, _)| std::fs::read_to_string(fixtures_dir.join(file)).unwrap())
+        .map(|content| serde_json::from_str::<Exchange>(&content).unwrap())
+        .map(|exchange| serde_json::from_str(&exchange.body).unwrap())
+        .collect();
+
+    check_lessons_shape(&courses);
+}
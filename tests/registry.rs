@@ -0,0 +1,119 @@
+// External libraries
+use async_trait::async_trait;
+use std::sync::Arc;
+
+// Internal modules
+use timetable::crawlers::{main::UniversityCrawler, store::CrawlerRegistry};
+use timetable::models::{
+    course::Course,
+    date_range::DateRange,
+    error::Error,
+    lesson::Lesson,
+    query::{CourseQuery, LessonQuery},
+    university::University,
+};
+
+/// Crawler that only has an id
+struct Stub(&'static str);
+
+#[async_trait]
+impl UniversityCrawler for Stub {
+    fn metadata(&self) -> University {
+        University {
+            id: self.0,
+            name: self.0,
+            country: "IT",
+            timezone: chrono_tz::Europe::Rome,
+            capabilities: vec![],
+            parameters: vec![],
+        }
+    }
+
+    async fn get_lessons(&self, _: LessonQuery, _: DateRange) -> Result<Vec<Lesson>, Error> {
+        Ok(vec![])
+    }
+
+    async fn get_courses(&self, _: CourseQuery) -> Result<Vec<Course>, Error> {
+        Ok(vec![])
+    }
+}
+
+/// Ids of the crawlers enabled out of `unicam`, `unimc` and `univpm`
+fn enabled(enabled: Option<&str>, disabled: Option<&str>) -> Vec<&'static str> {
+    let crawlers: Vec<Arc<dyn UniversityCrawler>> = vec![
+        Arc::new(Stub("univpm")),
+        Arc::new(Stub("unicam")),
+        Arc::new(Stub("unimc")),
+    ];
+
+    CrawlerRegistry::select(crawlers, enabled, disabled)
+        .all()
+        .map(|crawler| crawler.metadata().id)
+        .collect()
+}
+
+#[test]
+fn enables_every_crawler_by_default() {
+    assert_eq!(enabled(None, None), ["unicam", "unimc", "univpm"]);
+    assert_eq!(enabled(Some(""), Some("")), ["unicam", "unimc", "univpm"]);
+    assert_eq!(enabled(Some(" , "), None), ["unicam", "unimc", "univpm"]);
+}
+
+#[test]
+fn enables_only_the_listed_crawlers() {
+    assert_eq!(enabled(Some("unimc"), None), ["unimc"]);
+    assert_eq!(
+        enabled(Some(" UNIVPM ,unicam,"), None),
+        ["unicam", "univpm"]
+    );
+}
+
+#[test]
+fn disables_the_listed_crawlers() {
+    assert_eq!(enabled(None, Some("unicam")), ["unimc", "univpm"]);
+
+    // Disabling wins over enabling
+    assert_eq!(enabled(Some("unicam,unimc"), Some("Unimc")), ["unicam"]);
+    assert!(enabled(Some("unicam"), Some("unicam")).is_empty());
+}
+
+#[test]
+fn ignores_unknown_crawlers() {
+    assert_eq!(enabled(Some("unicam,unipg"), None), ["unicam"]);
+    assert_eq!(enabled(None, Some("unipg")), ["unicam", "unimc", "univpm"]);
+
+    // Only unknown crawlers enabled leaves none of the others
+    assert!(enabled(Some("unipg"), None).is_empty());
+}
+
+#[test]
+fn wraps_every_crawler_in_its_breaker() {
+    let registry = CrawlerRegistry::select(vec![Arc::new(Stub("unicam"))], None, None);
+
+    assert!(registry.get("unicam").is_some());
+    assert!(registry.get("unimc").is_none());
+    assert_eq!(
+        registry.breakers().map(|(id, _)| *id).collect::<Vec<_>>(),
+        ["unicam"]
+    );
+}
+
+#[test]
+fn checks_the_course_years_declared_by_the_crawler() {
+    let university = University {
+        parameters: LessonQuery::parameters(1..=3),
+        ..Stub("unimc").metadata()
+    };
+
+    assert!(university.check_course_year(0).is_err());
+    assert!(university.check_course_year(2).is_ok());
+    let error = university.check_course_year(4).unwrap_err();
+    assert_eq!(error.http_code, Some(400));
+    assert_eq!(
+        error.message.as_deref(),
+        Some("course_year must be a number from 1 to 3")
+    );
+
+    // Without a declared range any year goes
+    assert!(Stub("unimc").metadata().check_course_year(9).is_ok());
+}
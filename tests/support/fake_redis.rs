@@ -0,0 +1,316 @@
+// External libraries
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// In-memory stand-in for Redis speaking RESP2 on a local port.
+/// It implements the commands used by the server, keys never expire
+/// and the only Lua scripts it knows are the extension and the release of the crawl lock.
+/// Clones share the same data.
+#[derive(Clone)]
+pub struct FakeRedis {
+    url: String,
+    store: Arc<Mutex<Store>>,
+}
+
+#[derive(Default)]
+struct Store {
+    values: HashMap<Vec<u8>, Value>,
+    script_loaded: bool,
+}
+
+enum Value {
+    String(Vec<u8>),
+    SortedSet(HashMap<Vec<u8>, f64>),
+}
+
+enum Reply {
+    Status(&'static str),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Vec<Reply>),
+}
+
+impl FakeRedis {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}/", listener.local_addr().unwrap());
+        let store = Arc::new(Mutex::new(Store::default()));
+
+        let shared = store.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let store = shared.clone();
+                thread::spawn(move || serve(stream, store));
+            }
+        });
+
+        Self { url, store }
+    }
+
+    pub async fn connection(&self) -> ConnectionManager {
+        let client = redis::Client::open(self.url.as_str()).unwrap();
+        ConnectionManager::new(client).await.unwrap()
+    }
+
+    /// Names of the keys starting with `prefix`, sorted
+    pub fn keys(&self, prefix: &str) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .store
+            .lock()
+            .unwrap()
+            .values
+            .keys()
+            .map(|key| String::from_utf8_lossy(key).to_string())
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Value of a string key, bypassing the protocol
+    pub fn get(&self, key: &str) -> Option<String> {
+        match self.store.lock().unwrap().values.get(key.as_bytes()) {
+            Some(Value::String(value)) => Some(String::from_utf8_lossy(value).to_string()),
+            _ => None,
+        }
+    }
+
+    /// Overwrites a string key, bypassing the protocol
+    pub fn set(&self, key: &str, value: &str) {
+        self.store.lock().unwrap().values.insert(
+            key.as_bytes().to_vec(),
+            Value::String(value.as_bytes().to_vec()),
+        );
+    }
+}
+
+fn serve(stream: TcpStream, store: Arc<Mutex<Store>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut transaction: Option<Vec<Vec<Vec<u8>>>> = None;
+
+    while let Some(command) = read_command(&mut reader) {
+        let name = command
+            .first()
+            .map(|name| String::from_utf8_lossy(name).to_uppercase())
+            .unwrap_or_default();
+
+        let reply = match (name.as_str(), transaction.as_mut()) {
+            ("MULTI", _) => {
+                transaction = Some(vec![]);
+                Reply::Status("OK")
+            }
+            ("EXEC", Some(_)) => {
+                let queued = transaction.take().unwrap();
+                let mut store = store.lock().unwrap();
+                Reply::Array(
+                    queued
+                        .into_iter()
+                        .map(|command| execute(&mut store, command))
+                        .collect(),
+                )
+            }
+            (_, Some(queued)) => {
+                queued.push(command);
+                Reply::Status("QUEUED")
+            }
+            _ => execute(&mut store.lock().unwrap(), command),
+        };
+
+        let mut buffer = vec![];
+        encode(&reply, &mut buffer);
+        if writer.write_all(&buffer).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads a command sent as an array of bulk strings, `None` once the client is gone
+fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+    let count: usize = read_line(reader)?.strip_prefix('*')?.parse().ok()?;
+
+    (0..count)
+        .map(|_| {
+            let length: usize = read_line(reader)?.strip_prefix('$')?.parse().ok()?;
+            let mut argument = vec![0; length + 2];
+            reader.read_exact(&mut argument).ok()?;
+            argument.truncate(length);
+            Some(argument)
+        })
+        .collect()
+}
+
+fn read_line(reader: &mut BufReader<TcpStream>) -> Option<String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end().to_string()),
+    }
+}
+
+fn execute(store: &mut Store, command: Vec<Vec<u8>>) -> Reply {
+    let name = String::from_utf8_lossy(&command[0]).to_uppercase();
+    let args = &command[1..];
+    let text = |index: usize| String::from_utf8_lossy(&args[index]).to_string();
+
+    match name.as_str() {
+        "PING" => Reply::Status("PONG"),
+
+        "GET" => match store.values.get(&args[0]) {
+            Some(Value::String(value)) => Reply::Bulk(Some(value.clone())),
+            Some(Value::SortedSet(_)) => wrong_type(),
+            None => Reply::Bulk(None),
+        },
+
+        "SET" => {
+            let only_if_missing = args[2..].iter().any(|arg| arg.eq_ignore_ascii_case(b"NX"));
+            if only_if_missing && store.values.contains_key(&args[0]) {
+                return Reply::Bulk(None);
+            }
+            store
+                .values
+                .insert(args[0].clone(), Value::String(args[1].clone()));
+            Reply::Status("OK")
+        }
+
+        "SETEX" => {
+            store
+                .values
+                .insert(args[0].clone(), Value::String(args[2].clone()));
+            Reply::Status("OK")
+        }
+
+        "DEL" => Reply::Integer(
+            args.iter()
+                .filter(|key| store.values.remove(*key).is_some())
+                .count() as i64,
+        ),
+
+        "EXISTS" => Reply::Integer(
+            args.iter()
+                .filter(|key| store.values.contains_key(*key))
+                .count() as i64,
+        ),
+
+        "EXPIRE" => Reply::Integer(store.values.contains_key(&args[0]) as i64),
+
+        "INCR" | "INCRBY" => {
+            let increment: i64 = match name.as_str() {
+                "INCRBY" => text(1).parse().unwrap_or(0),
+                _ => 1,
+            };
+            let current = match store.values.get(&args[0]) {
+                Some(Value::String(value)) => String::from_utf8_lossy(value).parse().unwrap_or(0),
+                Some(Value::SortedSet(_)) => return wrong_type(),
+                None => 0,
+            };
+            store.values.insert(
+                args[0].clone(),
+                Value::String((current + increment).to_string().into_bytes()),
+            );
+            Reply::Integer(current + increment)
+        }
+
+        "ZINCRBY" => {
+            let increment: f64 = text(1).parse().unwrap_or(0.0);
+            let entry = store
+                .values
+                .entry(args[0].clone())
+                .or_insert_with(|| Value::SortedSet(HashMap::new()));
+            let Value::SortedSet(set) = entry else {
+                return wrong_type();
+            };
+            let score = set.entry(args[2].clone()).or_insert(0.0);
+            *score += increment;
+            Reply::Bulk(Some(score.to_string().into_bytes()))
+        }
+
+        "ZUNIONSTORE" => {
+            let count: usize = text(1).parse().unwrap_or(0);
+            let mut union: HashMap<Vec<u8>, f64> = HashMap::new();
+            for key in &args[2..2 + count] {
+                if let Some(Value::SortedSet(set)) = store.values.get(key) {
+                    for (member, score) in set {
+                        *union.entry(member.clone()).or_insert(0.0) += score;
+                    }
+                }
+            }
+            let length = union.len() as i64;
+            store
+                .values
+                .insert(args[0].clone(), Value::SortedSet(union));
+            Reply::Integer(length)
+        }
+
+        "ZREVRANGE" => {
+            let Some(Value::SortedSet(set)) = store.values.get(&args[0]) else {
+                return Reply::Array(vec![]);
+            };
+            let mut members: Vec<(&Vec<u8>, &f64)> = set.iter().collect();
+            members.sort_by(|a, b| b.1.total_cmp(a.1).then(b.0.cmp(a.0)));
+
+            let length = members.len() as i64;
+            let index = |value: i64| if value < 0 { length + value } else { value };
+            let start = index(text(1).parse().unwrap_or(0)).max(0);
+            let stop = index(text(2).parse().unwrap_or(-1)).min(length - 1);
+
+            Reply::Array(
+                (start..=stop)
+                    .map(|i| Reply::Bulk(Some(members[i as usize].0.clone())))
+                    .collect(),
+            )
+        }
+
+        "SCRIPT" => {
+            store.script_loaded = true;
+            Reply::Bulk(Some(b"0000000000000000000000000000000000000000".to_vec()))
+        }
+
+        // Crawl lock scripts, told apart by their arguments: KEYS[1] is the lock and ARGV[1] its token,
+        // the extension also has the TTL as ARGV[2] while the release deletes the lock
+        "EVALSHA" if !store.script_loaded => {
+            Reply::Error("NOSCRIPT No matching script. Please use EVAL.".into())
+        }
+        "EVALSHA" => match store.values.get(&args[2]) {
+            Some(Value::String(token)) if *token == args[3] => {
+                if args.len() == 4 {
+                    store.values.remove(&args[2]);
+                }
+                Reply::Integer(1)
+            }
+            _ => Reply::Integer(0),
+        },
+
+        // Connection setup (CLIENT SETINFO, SELECT, ...)
+        _ => Reply::Status("OK"),
+    }
+}
+
+fn wrong_type() -> Reply {
+    Reply::Error("WRONGTYPE Operation against a key holding the wrong kind of value".into())
+}
+
+fn encode(reply: &Reply, buffer: &mut Vec<u8>) {
+    match reply {
+        Reply::Status(status) => buffer.extend_from_slice(format!("+{}\r\n", status).as_bytes()),
+        Reply::Error(message) => buffer.extend_from_slice(format!("-{}\r\n", message).as_bytes()),
+        Reply::Integer(value) => buffer.extend_from_slice(format!(":{}\r\n", value).as_bytes()),
+        Reply::Bulk(None) => buffer.extend_from_slice(b"$-1\r\n"),
+        Reply::Bulk(Some(value)) => {
+            buffer.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+            buffer.extend_from_slice(value);
+            buffer.extend_from_slice(b"\r\n");
+        }
+        Reply::Array(items) => {
+            buffer.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+            for item in items {
+                encode(item, buffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// External libraries
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, http::StatusCode, web};
+use reqwest::Url;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Internal modules
+use timetable::crawlers::recorder::Exchange;
+
+/// Local HTTP server standing in for a university host, replaying its recorded exchanges.
+/// Requests without a matching exchange get a 404.
+/// Exchanges recorded with `CRAWLER_RECORD_DIR` can be copied to `tests/fixtures/<university>` as they are.
+/// The Unicam ones are still written by hand after the upstream format, the ignored test of `tests/record.rs` records them.
+pub struct FixtureServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    failure: Arc<Mutex<Option<u16>>>,
+    delay: Arc<Mutex<Duration>>,
+}
+
+struct Replay {
+    exchanges: Vec<Exchange>,
+    requests: Arc<Mutex<Vec<String>>>,
+    /// Status every request is answered with instead of its exchange
+    failure: Arc<Mutex<Option<u16>>>,
+    /// Time taken by every response
+    delay: Arc<Mutex<Duration>>,
+}
+
+impl FixtureServer {
+    /// Serves the exchanges in `tests/fixtures/<university>` recorded from `host`
+    pub async fn start(university: &str, host: &str) -> Self {
+        let directory = format!(
+            "{}/tests/fixtures/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            university
+        );
+
+        let exchanges: Vec<Exchange> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .map(|path| serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap())
+            .filter(|exchange: &Exchange| {
+                Url::parse(&exchange.url).unwrap().host_str() == Some(host)
+            })
+            .collect();
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let failure = Arc::new(Mutex::new(None));
+        let delay = Arc::new(Mutex::new(Duration::ZERO));
+        let replay = web::Data::new(Replay {
+            exchanges,
+            requests: requests.clone(),
+            failure: failure.clone(),
+            delay: delay.clone(),
+        });
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(replay.clone())
+                .default_service(web::to(replay_exchange))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let base_url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+
+        Self {
+            base_url,
+            requests,
+            failure,
+            delay,
+        }
+    }
+
+    /// Paths and queries received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Answers every following request with `status`, as a failing host would
+    pub fn fail_with(&self, status: u16) {
+        *self.failure.lock().unwrap() = Some(status);
+    }
+
+    /// Makes every following response take `delay`, as a slow host would
+    pub fn delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap() = delay;
+    }
+}
+
+async fn replay_exchange(request: HttpRequest, replay: web::Data<Replay>) -> HttpResponse {
+    replay
+        .requests
+        .lock()
+        .unwrap()
+        .push(format!("{}?{}", request.path(), request.query_string()));
+
+    let delay = *replay.delay.lock().unwrap();
+    actix_web::rt::time::sleep(delay).await;
+
+    if let Some(status) = *replay.failure.lock().unwrap() {
+        return HttpResponse::build(StatusCode::from_u16(status).unwrap()).finish();
+    }
+
+    let query: Vec<(String, String)> =
+        serde_urlencoded::from_str(request.query_string()).unwrap_or_default();
+
+    let exchange = replay.exchanges.iter().find(|exchange| {
+        Url::parse(&exchange.url).unwrap().path() == request.path() && exchange.query == query
+    });
+
+    match exchange {
+        Some(exchange) => HttpResponse::build(StatusCode::from_u16(exchange.status).unwrap())
+            .body(exchange.body.clone()),
+        None => {
+            HttpResponse::NotFound().body(format!("No fixture for {} {:?}", request.path(), query))
+        }
+    }
+}
@@ -0,0 +1,325 @@
+// Checks of the exported calendars against RFC 5545, covering what the exports rely on:
+// content lines, folding, component nesting, required properties and value formats.
+
+/// Properties every component must have exactly once
+const REQUIRED: &[(&str, &[&str])] = &[
+    ("VCALENDAR", &["VERSION", "PRODID"]),
+    ("VEVENT", &["UID", "DTSTAMP", "DTSTART"]),
+    ("VTIMEZONE", &["TZID"]),
+    ("STANDARD", &["DTSTART", "TZOFFSETFROM", "TZOFFSETTO"]),
+    ("DAYLIGHT", &["DTSTART", "TZOFFSETFROM", "TZOFFSETTO"]),
+    ("VALARM", &["ACTION", "TRIGGER"]),
+];
+
+/// Properties a component can have at most once
+const SINGLE: &[(&str, &[&str])] = &[
+    ("VCALENDAR", &["CALSCALE", "METHOD", "REFRESH-INTERVAL"]),
+    (
+        "VEVENT",
+        &[
+            "DTEND",
+            "SUMMARY",
+            "DESCRIPTION",
+            "LOCATION",
+            "GEO",
+            "STATUS",
+            "ORGANIZER",
+        ],
+    ),
+];
+
+struct Component {
+    name: String,
+    properties: Vec<Property>,
+    children: Vec<String>,
+}
+
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+/// Joins folded lines back together (RFC 5545 §3.1)
+pub fn unfold(ics: &str) -> String {
+    ics.replace("\r\n ", "").replace("\r\n\t", "")
+}
+
+/// Panics with the offending line at the first violation
+pub fn validate(ics: &str) {
+    // Content lines: CRLF only, at most 75 octets each
+    assert!(ics.ends_with("\r\n"), "the calendar must end with CRLF");
+    for line in ics[..ics.len() - 2].split("\r\n") {
+        assert!(
+            !line.contains(['\r', '\n']),
+            "bare line break in {:?}",
+            line
+        );
+        assert!(
+            line.len() <= 75,
+            "line of {} octets: {:?}",
+            line.len(),
+            line
+        );
+        assert!(!line.is_empty(), "empty line");
+    }
+
+    let unfolded = unfold(ics);
+    let mut stack: Vec<Component> = vec![];
+    let mut defined_tzids: Vec<String> = vec![];
+    let mut referenced_tzids: Vec<String> = vec![];
+    let mut calendars = 0;
+    let mut attendees = false;
+
+    for line in unfolded[..unfolded.len() - 2].split("\r\n") {
+        let property = parse_line(line);
+
+        match property.name.as_str() {
+            "BEGIN" => {
+                assert!(
+                    !stack.is_empty() || property.value == "VCALENDAR",
+                    "{} outside of VCALENDAR",
+                    property.value
+                );
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(property.value.clone());
+                }
+                stack.push(Component {
+                    name: property.value,
+                    properties: vec![],
+                    children: vec![],
+                });
+            }
+
+            "END" => {
+                let component = stack.pop().expect("END without BEGIN");
+                assert_eq!(component.name, property.value, "mismatched END");
+                check_component(&component);
+                if component.name == "VTIMEZONE" {
+                    defined_tzids.push(value_of(&component, "TZID").unwrap().to_string());
+                }
+                if component.name == "VEVENT" {
+                    attendees |= component.properties.iter().any(|p| p.name == "ATTENDEE");
+                }
+                if component.name == "VCALENDAR" {
+                    calendars += 1;
+                    // RFC 5546 §3.2.1: published events have no attendees
+                    assert!(
+                        !(attendees && value_of(&component, "METHOD") == Some("PUBLISH")),
+                        "ATTENDEE in a PUBLISH calendar"
+                    );
+                }
+            }
+
+            _ => {
+                let component = stack.last_mut().expect("property outside of any component");
+                check_value(&component.name, &property);
+                referenced_tzids.extend(
+                    property
+                        .parameters
+                        .iter()
+                        .filter(|(name, _)| name == "TZID")
+                        .map(|(_, value)| value.clone()),
+                );
+                component.properties.push(property);
+            }
+        }
+    }
+
+    assert!(stack.is_empty(), "unclosed components");
+    assert_eq!(calendars, 1, "exactly one VCALENDAR expected");
+    for tzid in referenced_tzids {
+        assert!(
+            defined_tzids.contains(&tzid),
+            "TZID {} has no VTIMEZONE",
+            tzid
+        );
+    }
+}
+
+/// Splits `NAME;PARAM=value;PARAM="quoted":value`, colons and semicolons in quoted parameters don't count
+fn parse_line(line: &str) -> Property {
+    let mut parts: Vec<String> = vec![String::new()];
+    let mut quoted = false;
+    let mut value_start = None;
+
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(String::new());
+                continue;
+            }
+            ':' if !quoted => {
+                value_start = Some(index + 1);
+                break;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+
+    let value_start = value_start.unwrap_or_else(|| panic!("no value in {:?}", line));
+    let name = parts.remove(0);
+    assert!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-'),
+        "invalid property name in {:?}",
+        line
+    );
+
+    let parameters = parts
+        .into_iter()
+        .map(|parameter| {
+            let (name, value) = parameter
+                .split_once('=')
+                .unwrap_or_else(|| panic!("invalid parameter in {:?}", line));
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => quoted
+                    .strip_suffix('"')
+                    .unwrap_or_else(|| panic!("unterminated quote in {:?}", line)),
+                None => {
+                    assert!(!value.contains('"'), "stray quote in {:?}", line);
+                    value
+                }
+            };
+            (name.to_string(), value.to_string())
+        })
+        .collect();
+
+    let value = line[value_start..].to_string();
+    assert!(!value.is_empty(), "empty value in {:?}", line);
+
+    Property {
+        name,
+        parameters,
+        value,
+    }
+}
+
+fn check_component(component: &Component) {
+    let count = |name: &str| {
+        component
+            .properties
+            .iter()
+            .filter(|property| property.name == name)
+            .count()
+    };
+
+    for (_, required) in REQUIRED.iter().filter(|(name, _)| *name == component.name) {
+        for property in *required {
+            assert_eq!(
+                count(property),
+                1,
+                "{} must have exactly one {}",
+                component.name,
+                property
+            );
+        }
+    }
+    for (_, single) in SINGLE.iter().filter(|(name, _)| *name == component.name) {
+        for property in *single {
+            assert!(
+                count(property) <= 1,
+                "{} has more than one {}",
+                component.name,
+                property
+            );
+        }
+    }
+
+    match component.name.as_str() {
+        "VCALENDAR" => assert_eq!(value_of(component, "VERSION"), Some("2.0")),
+        "VTIMEZONE" => assert!(
+            component
+                .children
+                .iter()
+                .any(|child| child == "STANDARD" || child == "DAYLIGHT"),
+            "VTIMEZONE without observances"
+        ),
+        // RFC 5545 §3.8.4.3: group scheduled components have an organizer
+        "VEVENT" if count("ATTENDEE") > 0 => {
+            assert_eq!(
+                count("ORGANIZER"),
+                1,
+                "VEVENT with attendees needs an ORGANIZER"
+            )
+        }
+        "VALARM" if value_of(component, "ACTION") == Some("DISPLAY") => {
+            assert_eq!(count("DESCRIPTION"), 1, "display alarms need a description")
+        }
+        _ => {}
+    }
+}
+
+fn check_value(component: &str, property: &Property) {
+    let value = property.value.as_str();
+    let has_tzid = property.parameters.iter().any(|(name, _)| name == "TZID");
+
+    match property.name.as_str() {
+        "DTSTAMP" => assert!(is_datetime(value, true), "DTSTAMP must be UTC: {}", value),
+        "DTSTART" | "DTEND" if component == "STANDARD" || component == "DAYLIGHT" => {
+            assert!(
+                is_datetime(value, false),
+                "observances start in local time: {}",
+                value
+            )
+        }
+        "DTSTART" | "DTEND" => assert!(
+            is_datetime(value, !has_tzid),
+            "{} must be local with a TZID or UTC without: {}",
+            property.name,
+            value
+        ),
+        "TZOFFSETFROM" | "TZOFFSETTO" => assert!(
+            (value.len() == 5 || value.len() == 7)
+                && value.starts_with(['+', '-'])
+                && value[1..].chars().all(|c| c.is_ascii_digit()),
+            "invalid UTC offset {}",
+            value
+        ),
+        "GEO" => {
+            let (latitude, longitude) = value.split_once(';').expect("GEO needs two floats");
+            assert!(latitude.parse::<f64>().is_ok() && longitude.parse::<f64>().is_ok());
+        }
+        "STATUS" => assert!(["TENTATIVE", "CONFIRMED", "CANCELLED"].contains(&value)),
+        "TRIGGER" | "REFRESH-INTERVAL" | "X-PUBLISHED-TTL" => {
+            assert!(
+                value.trim_start_matches(['-', '+']).starts_with('P'),
+                "invalid duration {}",
+                value
+            )
+        }
+        _ => {}
+    }
+}
+
+/// `YYYYMMDDTHHMMSS`, followed by `Z` when in UTC
+fn is_datetime(value: &str, utc: bool) -> bool {
+    let local = match utc {
+        true => match value.strip_suffix('Z') {
+            Some(local) => local,
+            None => return false,
+        },
+        false => value,
+    };
+
+    local.len() == 15
+        && local.char_indices().all(|(index, c)| {
+            if index == 8 {
+                c == 'T'
+            } else {
+                c.is_ascii_digit()
+            }
+        })
+}
+
+fn value_of<'a>(component: &'a Component, name: &str) -> Option<&'a str> {
+    component
+        .properties
+        .iter()
+        .find(|property| property.name == name)
+        .map(|property| property.value.as_str())
+}
@@ -0,0 +1,133 @@
+// Every test crate only uses part of the support code
+#![allow(dead_code)]
+
+pub mod fake_redis;
+pub mod fixtures;
+pub mod ics;
+
+// External libraries
+use actix_http::Request;
+use actix_web::{
+    App, Error,
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    test,
+    web::Data,
+};
+use chrono::Utc;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+// Internal modules
+use fake_redis::FakeRedis;
+use fixtures::FixtureServer;
+use timetable::api::main::configure;
+use timetable::cache::store::Caches;
+use timetable::crawlers::{rooms::RoomCatalog, store::CrawlerRegistry, unicam::UnicamCrawler};
+use timetable::profiles::store::{ProfileConfig, ProfileStore};
+use timetable::scheduler::prewarm::{PrewarmConfig, Prewarmer};
+
+/// Stand-ins of the Unicam hosts and of Redis, kept around to inspect the requests they received and what was cached
+pub struct Unicam {
+    pub courses: FixtureServer,
+    pub lessons: FixtureServer,
+    pub redis: FakeRedis,
+}
+
+/// Builds the whole API with Unicam replayed from its fixtures and an in-memory Redis
+pub async fn unicam_app() -> (
+    impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    Unicam,
+) {
+    let unicam = Unicam {
+        courses: FixtureServer::start("unicam", "orarilezioni.unicam.it").await,
+        lessons: FixtureServer::start("unicam", "unifare.unicam.it").await,
+        redis: FakeRedis::start(),
+    };
+
+    let app = build_app(
+        unicam.courses.base_url.clone(),
+        unicam.lessons.base_url.clone(),
+        unicam.redis.clone(),
+    )
+    .await;
+    (app, unicam)
+}
+
+impl Unicam {
+    /// Another instance of the whole API against the same hosts and Redis, as run by a second replica
+    pub async fn replica(
+        &self,
+    ) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+        build_app(
+            self.courses.base_url.clone(),
+            self.lessons.base_url.clone(),
+            self.redis.clone(),
+        )
+        .await
+    }
+
+    /// Registry with a crawler of its own against the same hosts, for the code running outside of the API
+    pub fn registry(&self) -> CrawlerRegistry {
+        unicam_registry(&self.courses.base_url, &self.lessons.base_url)
+    }
+}
+
+fn unicam_registry(courses_url: &str, lessons_url: &str) -> CrawlerRegistry {
+    let rooms = RoomCatalog::load(
+        format!(
+            "{}/tests/fixtures/rooms/unicam.json",
+            env!("CARGO_MANIFEST_DIR")
+        )
+        .as_ref(),
+    );
+
+    CrawlerRegistry::new(vec![Arc::new(
+        UnicamCrawler::with_base_urls(courses_url, lessons_url).with_room_catalog(rooms),
+    )])
+}
+
+async fn build_app(
+    courses_url: String,
+    lessons_url: String,
+    redis: FakeRedis,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let connection = redis.connection().await;
+    let registry = Data::new(unicam_registry(&courses_url, &lessons_url));
+    let caches = Data::new(Caches::new(connection.clone()));
+    let profiles = Data::new(ProfileStore::new(
+        connection.clone(),
+        ProfileConfig { ttl: 3600 },
+    ));
+    let prewarmer = Data::new(Prewarmer::new(
+        connection,
+        PrewarmConfig {
+            interval: 900,
+            top: 50,
+            window_days: 7,
+        },
+    ));
+
+    test::init_service(
+        App::new()
+            .app_data(registry)
+            .app_data(caches)
+            .app_data(prewarmer)
+            .app_data(profiles)
+            .configure(configure),
+    )
+    .await
+}
+
+/// Moves the cache entry `seconds` into the past, as if stored that long ago
+pub fn age(redis: &FakeRedis, key: &str, seconds: i64) {
+    let mut entry: Value = serde_json::from_str(&redis.get(key).unwrap()).unwrap();
+    entry["stored_at"] = json!(entry["stored_at"].as_i64().unwrap() - seconds);
+    redis.set(key, &entry.to_string());
+}
+
+/// Seconds since the cache entry was stored
+pub fn age_of(redis: &FakeRedis, key: &str) -> i64 {
+    let entry: Value = serde_json::from_str(&redis.get(key).unwrap()).unwrap();
+    Utc::now().timestamp() - entry["stored_at"].as_i64().unwrap()
+}
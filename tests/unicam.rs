@@ -0,0 +1,647 @@
+mod support;
+
+// External libraries
+use actix_web::{http::StatusCode, test};
+use serde_json::{Value, json};
+
+// Internal modules
+use support::{
+    ics::{unfold, validate},
+    unicam_app,
+};
+use timetable::crawlers::{
+    http::{HttpClient, HttpConfig},
+    recorder::Recording,
+};
+
+const LESSONS_QUERY: &str = "course_id=3042&course_year=1&from=2025-03-03&to=2025-03-09";
+
+#[actix_web::test]
+async fn lists_the_university() {
+    let (app, _unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get().uri("/timetable").to_request();
+    let universities: Value = test::call_and_read_body_json(&app, request).await;
+
+    assert_eq!(universities[0]["id"], "unicam");
+    assert_eq!(universities[0]["timezone"], "Europe/Rome");
+}
+
+#[actix_web::test]
+async fn crawls_and_caches_courses() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/courses")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "MISS");
+
+    let courses: Value = test::read_body_json(response).await;
+    assert_eq!(courses.as_array().unwrap().len(), 5);
+    assert_eq!(courses[0]["id"], "3042");
+    assert_eq!(courses[0]["code"], "L-31");
+    assert_eq!(courses[0]["name"], "Informatica");
+    assert_eq!(courses[0]["category"], "Scuola di Scienze e Tecnologie");
+    assert!(courses[0].get("attributes").is_none());
+    assert_eq!(courses[3]["category"], "Scuola di Giurisprudenza");
+
+    // Reordered and unquoted attributes, dashes in the name
+    assert_eq!(courses[2]["id"], "3051");
+    assert_eq!(courses[2]["code"], "LM-18");
+    assert_eq!(
+        courses[2]["name"],
+        "Computer Science - Software Engineering"
+    );
+    assert_eq!(courses[2]["attributes"]["tipo"], "LM");
+    assert_eq!(courses[2]["attributes"]["lingua"], "EN");
+    assert_eq!(courses[2]["attributes"]["sede"], "Camerino");
+
+    // Unclosed option and en dash separator
+    assert_eq!(courses[3]["code"], "LMG/01");
+    assert_eq!(courses[3]["name"], "Giurisprudenza");
+
+    // Entities
+    assert_eq!(courses[4]["name"], "Architettura & Design per l'Ambiente");
+    assert_eq!(courses[4]["attributes"]["sede"], "Ascoli Piceno");
+
+    // The second request is served from Redis
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/courses")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "HIT");
+    assert_eq!(unicam.courses.requests().len(), 1);
+}
+
+#[actix_web::test]
+async fn crawls_lessons_in_the_requested_range() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons?{}", LESSONS_QUERY))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let lessons: Value = test::read_body_json(response).await;
+    assert_eq!(lessons.as_array().unwrap().len(), 4);
+
+    // Upstream times are local to Europe/Rome (UTC+1 in March)
+    assert_eq!(lessons[0]["id"], "unicam-48211");
+    assert_eq!(lessons[0]["subject"], "PROGRAMMAZIONE");
+    assert_eq!(lessons[0]["starts_at"], "2025-03-03T08:00:00Z");
+    assert_eq!(lessons[0]["ends_at"], "2025-03-03T10:00:00Z");
+
+    // Days start at midnight in Rome and the upstream end date is exclusive
+    let requests = unicam.lessons.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].contains("start=2025-03-03T00%3A00%3A00%2B01%3A00"));
+    assert!(requests[0].contains("end=2025-03-10T00%3A00%3A00%2B01%3A00"));
+
+    // Each side of a daylight saving time change has its own offset
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3042&course_year=1&from=2025-03-24&to=2025-03-30")
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let requests = unicam.lessons.requests();
+    assert!(requests[1].contains("start=2025-03-24T00%3A00%3A00%2B01%3A00"));
+    assert!(requests[1].contains("end=2025-03-31T00%3A00%3A00%2B02%3A00"));
+}
+
+#[actix_web::test]
+async fn parses_lesson_descriptions() {
+    let (app, _unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons?{}", LESSONS_QUERY))
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+
+    // Completed from the building entry of the room catalog
+    assert_eq!(
+        lessons[0]["location"],
+        json!({
+            "room": "AULA A",
+            "building": "Polo Lodovici",
+            "campus": "Camerino",
+            "address": "Via Madonna delle Carceri 9, Camerino",
+            "latitude": 43.139,
+            "longitude": 13.0687
+        })
+    );
+    assert_eq!(
+        lessons[0]["teachers"],
+        json!([{ "name": "ROSSI MARIO", "email": "mario.rossi@unicam.it" }])
+    );
+    assert_eq!(lessons[0]["description"], Value::Null);
+
+    // Teachers on separate lines
+    assert_eq!(
+        lessons[1]["teachers"],
+        json!([{ "name": "BIANCHI LUCA" }, { "name": "VERDI ANNA" }])
+    );
+
+    // Notes with an online link, the room entry of the catalog wins over its building
+    assert_eq!(lessons[2]["teachers"][0]["name"], "NERI PAOLA");
+    assert_eq!(lessons[2]["location"]["latitude"], 43.1393);
+    assert_eq!(
+        lessons[2]["location"]["online_url"],
+        "https://teams.microsoft.com/l/meetup-join/19%3ameeting_unicam"
+    );
+    assert_eq!(
+        lessons[2]["description"],
+        "Lezione anche in streaming su Microsoft Teams\nhttps://teams.microsoft.com/l/meetup-join/19%3ameeting_unicam"
+    );
+
+    // No teachers section
+    assert_eq!(
+        lessons[3]["location"],
+        json!({ "room": "Auditorium Benedetto XIII" })
+    );
+    assert_eq!(lessons[3]["teachers"], json!([]));
+}
+
+#[actix_web::test]
+async fn filters_lessons_by_teacher() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons?{}&teacher=verdi",
+            LESSONS_QUERY
+        ))
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(lessons.as_array().unwrap().len(), 1);
+    assert_eq!(lessons[0]["subject"], "ANALISI MATEMATICA");
+
+    // Every filter is served from the same cached timetable
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons.ics?{}&teacher=Rossi",
+            LESSONS_QUERY
+        ))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "HIT");
+    assert_eq!(unicam.lessons.requests().len(), 1);
+
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    validate(&body);
+    let body = unfold(&body);
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 1);
+    assert!(body.contains("ORGANIZER;CN=UNICAM:urn:timetable:unicam\r\n"));
+    assert!(!body.contains("METHOD:"));
+    assert!(body.contains(
+        "ATTENDEE;CN=\"ROSSI MARIO\";CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT:mailto:mario.rossi@unicam.it\r\n"
+    ));
+}
+
+#[actix_web::test]
+async fn filters_lessons_by_subject_room_and_time() {
+    let (app, unicam) = unicam_app().await;
+
+    for (filter, expected) in [
+        (
+            "subjects=programmazione,Analisi",
+            vec!["unicam-48211", "unicam-48212"],
+        ),
+        (
+            "exclude_subjects=seminario",
+            vec!["unicam-48211", "unicam-48212", "unicam-48230"],
+        ),
+        ("room=aula%20c", vec!["unicam-48230"]),
+        (
+            "room=lodovici&exclude_subjects=analisi",
+            vec!["unicam-48211", "unicam-48230"],
+        ),
+        ("weekdays=mon,fri", vec!["unicam-48211", "unicam-48241"]),
+        // Times of day are local to Europe/Rome
+        ("after=14:00", vec!["unicam-48212", "unicam-48241"]),
+        ("before=13:00", vec!["unicam-48211", "unicam-48230"]),
+    ] {
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/timetable/unicam/lessons?{}&{}",
+                LESSONS_QUERY, filter
+            ))
+            .to_request();
+        let lessons: Value = test::call_and_read_body_json(&app, request).await;
+        let ids: Vec<&str> = lessons
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lesson| lesson["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, expected, "{}", filter);
+    }
+
+    // A filtered calendar can be subscribed to as it is
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons.ics?{}&subjects=analisi,seminario&after=15:00",
+            LESSONS_QUERY
+        ))
+        .to_request();
+    let body = String::from_utf8(
+        test::read_body(test::call_service(&app, request).await)
+            .await
+            .to_vec(),
+    )
+    .unwrap();
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 1);
+    assert!(body.contains("SUMMARY:SEMINARIO DI ORIENTAMENTO"));
+    assert_eq!(unicam.lessons.requests().len(), 1);
+
+    for filter in [
+        "after=14:00&before=09:00",
+        "weekdays=someday",
+        "after=25:00",
+    ] {
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/timetable/unicam/lessons?{}&{}",
+                LESSONS_QUERY, filter
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", filter);
+    }
+}
+
+#[actix_web::test]
+async fn classifies_lessons() {
+    let (app, _unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons?{}", LESSONS_QUERY))
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+
+    assert_eq!(lessons[0]["kind"], "lecture");
+    assert_eq!(lessons[0]["status"], "scheduled");
+    // Cancelled in the notes
+    assert_eq!(lessons[1]["status"], "cancelled");
+    // "ELABORATORI" is not a laboratory
+    assert_eq!(lessons[2]["kind"], "lecture");
+    assert_eq!(lessons[3]["kind"], "seminar");
+
+    // A lab in its class names, drawn in grey like any other course
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3051&course_year=1&from=2025-03-03&to=2025-03-09")
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(lessons[0]["id"], "unicam-51107");
+    assert_eq!(lessons[0]["kind"], "lab");
+    assert_eq!(lessons[0]["status"], "scheduled");
+
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons?{}&kind=seminar",
+            LESSONS_QUERY
+        ))
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(lessons.as_array().unwrap().len(), 1);
+    assert_eq!(lessons[0]["id"], "unicam-48241");
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons.ics?{}", LESSONS_QUERY))
+        .to_request();
+    let body = String::from_utf8(
+        test::read_body(test::call_service(&app, request).await)
+            .await
+            .to_vec(),
+    )
+    .unwrap();
+    assert_eq!(body.matches("CATEGORIES:Lecture\r\n").count(), 3);
+    assert_eq!(body.matches("CATEGORIES:Seminar\r\n").count(), 1);
+    assert_eq!(body.matches("STATUS:CANCELLED\r\n").count(), 1);
+
+    let request = test::TestRequest::get()
+        .uri(&format!(
+            "/timetable/unicam/lessons?{}&kind=party",
+            LESSONS_QUERY
+        ))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn merges_the_timetables_of_several_courses() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3042&course_year=1&courses=3051:1,3042:1&from=2025-03-03&to=2025-03-09")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "MISS");
+
+    // Sorted by start, the lesson shared by both courses appears once
+    let lessons: Value = test::read_body_json(response).await;
+    let ids: Vec<&str> = lessons
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lesson| lesson["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            "unicam-48211",
+            "unicam-48212",
+            "unicam-51107",
+            "unicam-48230",
+            "unicam-48241"
+        ]
+    );
+    assert_eq!(lessons[0]["courses"], json!([{ "id": "3042", "year": 1 }]));
+    assert_eq!(lessons[2]["courses"], json!([{ "id": "3051", "year": 1 }]));
+    assert_eq!(
+        lessons[3]["courses"],
+        json!([{ "id": "3042", "year": 1 }, { "id": "3051", "year": 1 }])
+    );
+
+    // Each course was crawled once and is cached on its own
+    assert_eq!(unicam.lessons.requests().len(), 2);
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons.ics?courses=3051:1&from=2025-03-03&to=2025-03-09")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "HIT");
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 2);
+    assert_eq!(unicam.lessons.requests().len(), 2);
+
+    for query in [
+        "course_id=3042&from=2025-03-03",
+        "from=2025-03-03",
+        "courses=3042&from=2025-03-03",
+        "courses=1:1,2:1,3:1,4:1,5:1,6:1,7:1,8:1,9:1",
+    ] {
+        let request = test::TestRequest::get()
+            .uri(&format!("/timetable/unicam/lessons?{}", query))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn serves_saved_profiles_at_short_urls() {
+    let (app, unicam) = unicam_app().await;
+    let selection = json!({
+        "university": "UniCam",
+        "courses": ["3042:1", "3051:1"],
+        "from": "2025-03-03",
+        "to": "2025-03-09",
+        "subjects": ["analisi", "software"],
+        "reminders": [15]
+    });
+
+    let request = test::TestRequest::post()
+        .uri("/timetable/profiles")
+        .set_json(&selection)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = test::read_body_json(response).await;
+    let token = created["token"].as_str().unwrap().to_string();
+    assert_eq!(token.len(), 16);
+    assert_eq!(created["ics"], format!("/timetable/p/{}.ics", token));
+
+    // Tokens are random, the same selection gets another one
+    let request = test::TestRequest::post()
+        .uri("/timetable/profiles")
+        .set_json(&selection)
+        .to_request();
+    let again: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(again["token"].as_str().unwrap().len(), 16);
+    assert_ne!(again["token"], token);
+    assert!(unicam.lessons.requests().is_empty());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/p/{}.json", token))
+        .to_request();
+    let lessons: Value = test::call_and_read_body_json(&app, request).await;
+    let ids: Vec<&str> = lessons
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lesson| lesson["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["unicam-48212", "unicam-51107"]);
+
+    // Rendered through the same cache as the lessons endpoints
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/p/{}.ics", token))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get("X-Cache").unwrap(), "HIT");
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    validate(&body);
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 2);
+    assert_eq!(body.matches("TRIGGER:-PT15M\r\n").count(), 2);
+    assert_eq!(unicam.lessons.requests().len(), 2);
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/p/0000000000.ics")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    for (selection, status) in [
+        (
+            json!({ "university": "nowhere", "courses": ["3042:1"] }),
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            json!({ "university": "unicam", "courses": ["3042:9"] }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "university": "unicam", "course_id": "3042" }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "university": "unicam", "courses": ["3042:1"], "reminders": [20000] }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (json!({ "courses": ["3042:1"] }), StatusCode::BAD_REQUEST),
+    ] {
+        let request = test::TestRequest::post()
+            .uri("/timetable/profiles")
+            .set_json(&selection)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), status, "{}", selection);
+    }
+}
+
+#[actix_web::test]
+async fn serves_a_crawled_lesson_by_id() {
+    let (app, _unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons/unicam-48212")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons?{}", LESSONS_QUERY))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons/unicam-48212")
+        .to_request();
+    let lesson: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(lesson["subject"], "ANALISI MATEMATICA");
+}
+
+#[actix_web::test]
+async fn exports_lessons_as_ics() {
+    let (app, _unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons.ics?{}", LESSONS_QUERY))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/calendar"
+    );
+
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    validate(&body);
+    let body = unfold(&body);
+    assert!(body.starts_with("BEGIN:VCALENDAR"));
+    assert!(body.contains("REFRESH-INTERVAL;VALUE=DURATION:PT6H\r\nX-PUBLISHED-TTL:PT6H\r\n"));
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 4);
+    assert!(body.contains("UID:timetable-unicam-unicam-48230"));
+
+    // Local times, along with the timezone they refer to
+    assert!(body.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Rome\r\n"));
+    assert!(body.contains("DTSTART;TZID=Europe/Rome:20250306T110000\r\n"));
+    assert!(body.contains("DTEND;TZID=Europe/Rome:20250306T130000\r\n"));
+
+    // Nothing to describe, no empty properties
+    let seminar = body.split("BEGIN:VEVENT").nth(4).unwrap();
+    assert!(seminar.contains("UID:timetable-unicam-unicam-48241"));
+    assert!(!seminar.contains("DESCRIPTION"));
+    assert!(!seminar.contains("ORGANIZER"));
+    assert!(body.contains(
+        "LOCATION:AULA A - Polo Lodovici\\, Via Madonna delle Carceri 9\\, Camerino\r\n"
+    ));
+    assert!(body.contains("GEO:43.139000;13.068700\r\n"));
+    assert!(body.contains("LOCATION:Auditorium Benedetto XIII\r\n"));
+}
+
+#[actix_web::test]
+async fn rejects_invalid_queries() {
+    let (app, unicam) = unicam_app().await;
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3042&course_year=9")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(response).await;
+    assert!(body["message"].as_str().unwrap().contains("from 0 to 5"));
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3042&course_year=first")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 367 days, one more than a request can cover
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=3042&course_year=1&from=2025-03-03&to=2026-03-04")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(response).await;
+    assert!(body["message"].as_str().unwrap().contains("366 days"));
+
+    // Undeclared parameters would otherwise make a new cache key each
+    let request = test::TestRequest::get()
+        .uri(&format!("/timetable/unicam/lessons?{}&x=1", LESSONS_QUERY))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(response).await;
+    assert!(body["message"].as_str().unwrap().contains("unknown parameters x"));
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/courses?x=1")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    assert!(unicam.lessons.requests().is_empty());
+    assert!(unicam.courses.requests().is_empty());
+}
+
+#[actix_web::test]
+async fn reports_upstream_failures_as_bad_gateway() {
+    let (app, _unicam) = unicam_app().await;
+
+    // No fixture was recorded for this course, the stand-in answers 404
+    let request = test::TestRequest::get()
+        .uri("/timetable/unicam/lessons?course_id=9999&course_year=1&from=2025-03-03&to=2025-03-09")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    let request = test::TestRequest::get()
+        .uri("/timetable/status")
+        .to_request();
+    let status: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(status["crawlers"]["unicam"]["state"], "closed");
+    assert_eq!(status["crawlers"]["unicam"]["consecutive_failures"], 1);
+}
+
+#[actix_web::test]
+async fn records_and_replays_upstream_exchanges() {
+    let (_app, unicam) = unicam_app().await;
+    let url = format!("{}/", unicam.courses.base_url);
+    let dir = std::env::temp_dir().join(format!("timetable-recording-{}", std::process::id()));
+
+    let recorder = HttpClient::new(HttpConfig {
+        recording: Recording::Record(dir.clone()),
+        ..HttpConfig::default()
+    });
+    let live = recorder.get(&url, &[]).await.unwrap();
+
+    // The replay never reaches the stand-in
+    let replayer = HttpClient::new(HttpConfig {
+        recording: Recording::Replay(dir.clone()),
+        ..HttpConfig::default()
+    });
+    assert_eq!(replayer.get(&url, &[]).await.unwrap(), live);
+    assert!(replayer.get(&url, &[("page", "2")]).await.is_err());
+    assert_eq!(unicam.courses.requests().len(), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // The checked-in fixtures can be replayed too
+    let replayer = HttpClient::new(HttpConfig {
+        recording: Recording::Replay(
+            format!("{}/tests/fixtures/unicam", env!("CARGO_MANIFEST_DIR")).into(),
+        ),
+        ..HttpConfig::default()
+    });
+    let body = replayer
+        .get("https://orarilezioni.unicam.it/", &[])
+        .await
+        .unwrap();
+    assert!(body.contains("<optgroup"));
+}